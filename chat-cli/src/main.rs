@@ -18,7 +18,7 @@ fn main() {
     let mut clock = Clock::new();
 
     // Create client
-    let mut client = Client::new(([127, 0, 0, 1], 59003), comp::Player::new("test".to_string()), None, 300)
+    let mut client = Client::new(([127, 0, 0, 1], 59003), comp::Player::new("test".to_string()), 300)
         .expect("Failed to create client instance");
 
     client.send_chat("Hello!".to_string());
@@ -35,6 +35,7 @@ fn main() {
         for event in events {
             match event {
                 Event::Chat(msg) => println!("[chat] {}", msg),
+                Event::Damage { .. } | Event::Death { .. } | Event::CharacterActionError(_) | Event::CharacterSelected => {},
             }
         }
 
@@ -1,14 +1,23 @@
 use vek::*;
+use common::comp::Gait;
 
 pub struct Input {
     // TODO: Use this type to manage client input
     pub move_dir: Vec2<f32>,
+    pub gait: Gait,
+    pub jump: bool,
+    pub glide: bool,
+    pub attack: bool,
 }
 
 impl Default for Input {
     fn default() -> Self {
         Input {
             move_dir: Vec2::zero(),
+            gait: Gait::Run,
+            jump: false,
+            glide: false,
+            attack: false,
         }
     }
 }
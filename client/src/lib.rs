@@ -13,23 +13,36 @@ pub use crate::{
 use std::{
     time::Duration,
     net::SocketAddr,
-    collections::HashSet,
+    collections::{HashMap, HashSet},
 };
 use vek::*;
 use threadpool::ThreadPool;
-use specs::Builder;
+use specs::{Builder, Join};
 use common::{
-    comp,
-    state::State,
-    terrain::TerrainChunk,
+    comp::{self, mount::MOUNT_REACH},
+    state::{State, FixedStep},
+    terrain::{Block, TerrainChunk},
     net::PostBox,
-    msg::{ClientMsg, ServerMsg},
+    msg::{CharacterInfo, ClientMsg, ServerMsg},
 };
 
 const SERVER_TIMEOUT: f64 = 20.0; // Seconds
 
 pub enum Event {
     Chat(String),
+    /// The player's character was damaged
+    Damage {
+        amount: u32,
+        source: Option<EcsEntity>,
+    },
+    /// The player's character died
+    Death {
+        killer: Option<EcsEntity>,
+    },
+    /// The server refused to create, delete or select a character
+    CharacterActionError(String),
+    /// The selected character has entered the world
+    CharacterSelected,
 }
 
 pub struct Client {
@@ -44,6 +57,12 @@ pub struct Client {
     view_distance: u64,
 
     pending_chunks: HashSet<Vec3<i32>>,
+    chunk_versions: HashMap<Vec3<i32>, u64>,
+
+    character_list: Option<Vec<CharacterInfo>>,
+
+    // How far into its attack the player was last tick, used to spot the start of new attacks
+    last_attack_time: Option<f32>,
 }
 
 impl Client {
@@ -52,7 +71,6 @@ impl Client {
     pub fn new<A: Into<SocketAddr>>(
         addr: A,
         player: comp::Player,
-        character: Option<comp::Character>,
         view_distance: u64,
     ) -> Result<Self, Error> {
 
@@ -61,13 +79,14 @@ impl Client {
         // Send connection request
         postbox.send_message(ClientMsg::Connect {
             player,
-            character,
         });
 
         // Wait for handshake from server
         let (state, player) = match postbox.next_message() {
-            Some(ServerMsg::Handshake { ecs_state, player_entity }) => {
-                let mut state = State::from_state_package(ecs_state);
+            Some(ServerMsg::Handshake { ecs_state, player_entity, time_of_day, day_length }) => {
+                let mut state = State::from_state_package(ecs_state).with_fixed_step(FixedStep::default());
+                state.set_time_of_day(time_of_day);
+                state.set_day_length(day_length);
                 let player_entity = state.ecs().entity_from_uid(player_entity).ok_or(Error::ServerWentMad)?;
                 (state, player_entity)
             },
@@ -88,6 +107,11 @@ impl Client {
             view_distance,
 
             pending_chunks: HashSet::new(),
+            chunk_versions: HashMap::new(),
+
+            character_list: None,
+
+            last_attack_time: None,
         })
     }
 
@@ -117,12 +141,146 @@ impl Client {
         self.tick
     }
 
+    /// Get the characters belonging to the player's account, if the server has sent them yet
+    #[allow(dead_code)]
+    pub fn character_list(&self) -> Option<&[CharacterInfo]> {
+        self.character_list.as_ref().map(|list| list.as_slice())
+    }
+
+    /// Ask the server for the characters belonging to the player's account
+    #[allow(dead_code)]
+    pub fn request_character_list(&mut self) {
+        self.postbox.send_message(ClientMsg::RequestCharacterList)
+    }
+
+    /// Ask the server to create a new character for the player's account
+    #[allow(dead_code)]
+    pub fn create_character(&mut self, name: String, character: comp::Character) {
+        self.postbox.send_message(ClientMsg::CreateCharacter { name, character })
+    }
+
+    /// Ask the server to delete one of the player's characters
+    #[allow(dead_code)]
+    pub fn delete_character(&mut self, name: String) {
+        self.postbox.send_message(ClientMsg::DeleteCharacter { name })
+    }
+
+    /// Enter the world with one of the player's characters
+    #[allow(dead_code)]
+    pub fn select_character(&mut self, name: String) {
+        self.postbox.send_message(ClientMsg::SelectCharacter { name })
+    }
+
     /// Send a chat message to the server
     #[allow(dead_code)]
     pub fn send_chat(&mut self, msg: String) {
         self.postbox.send_message(ClientMsg::Chat(msg))
     }
 
+    /// Ask the server to place a block at the provided position
+    #[allow(dead_code)]
+    pub fn set_block(&mut self, pos: Vec3<i32>, block: Block) {
+        self.postbox.send_message(ClientMsg::SetBlock { pos, block })
+    }
+
+    /// Ask the server to remove the block at the provided position
+    #[allow(dead_code)]
+    pub fn remove_block(&mut self, pos: Vec3<i32>) {
+        self.postbox.send_message(ClientMsg::RemoveBlock { pos })
+    }
+
+    /// Get a copy of the player's inventory, as last sent by the server
+    #[allow(dead_code)]
+    pub fn inventory(&self) -> Option<comp::Inventory> {
+        self.state.read_component_cloned(self.player)
+    }
+
+    /// Ask the server to move the contents of one inventory slot to another
+    #[allow(dead_code)]
+    pub fn move_inventory_slot(&mut self, from: usize, to: usize) {
+        self.postbox.send_message(ClientMsg::MoveInventorySlot { from, to })
+    }
+
+    /// Ask the server to split `amount` items off the stack in an inventory slot
+    #[allow(dead_code)]
+    pub fn split_inventory_slot(&mut self, slot: usize, amount: u32) {
+        self.postbox.send_message(ClientMsg::SplitInventorySlot { slot, amount })
+    }
+
+    /// Ask the server to drop the contents of an inventory slot into the world
+    #[allow(dead_code)]
+    pub fn drop_inventory_slot(&mut self, slot: usize) {
+        self.postbox.send_message(ClientMsg::DropInventorySlot { slot })
+    }
+
+    /// Ask the server to pick up a dropped item
+    #[allow(dead_code)]
+    pub fn pick_up(&mut self, entity: EcsEntity) {
+        if let Some(uid) = self.state.ecs().uid_from_entity(entity) {
+            self.postbox.send_message(ClientMsg::PickUp { entity: uid.into() })
+        }
+    }
+
+    /// Whether the player is currently riding a creature
+    #[allow(dead_code)]
+    pub fn is_riding(&self) -> bool {
+        self.state.read_storage::<comp::Rider>().get(self.player).is_some()
+    }
+
+    /// Ask the server to let the player ride a creature
+    #[allow(dead_code)]
+    pub fn mount(&mut self, entity: EcsEntity) {
+        if let Some(uid) = self.state.ecs().uid_from_entity(entity) {
+            self.postbox.send_message(ClientMsg::Mount { entity: uid.into() })
+        }
+    }
+
+    /// Ask the server to let the player off the creature they're riding
+    #[allow(dead_code)]
+    pub fn dismount(&mut self) {
+        self.postbox.send_message(ClientMsg::Dismount)
+    }
+
+    /// Dismount if the player is riding, otherwise try to mount the closest rideable creature
+    /// within reach
+    #[allow(dead_code)]
+    pub fn toggle_mount(&mut self) {
+        if self.is_riding() {
+            self.dismount();
+            return;
+        }
+
+        let player_pos = match self.state.read_component_cloned::<comp::phys::Pos>(self.player) {
+            Some(pos) => pos.0,
+            None => return,
+        };
+        let closest = {
+            let ecs = self.state.ecs().internal();
+            (
+                &ecs.entities(),
+                &ecs.read_storage::<comp::phys::Pos>(),
+                &ecs.read_storage::<comp::Body>(),
+                !&ecs.read_storage::<comp::Mount>(),
+            )
+                .join()
+                .filter(|(entity, pos, body, _)| {
+                    *entity != self.player &&
+                    body.mount_offset().is_some() &&
+                    pos.0.distance(player_pos) <= MOUNT_REACH
+                })
+                .min_by(|(_, a, _, _), (_, b, _, _)| {
+                    a.0.distance_squared(player_pos)
+                        .partial_cmp(&b.0.distance_squared(player_pos))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .map(|(entity, _, _, _)| entity)
+        };
+
+        if let Some(entity) = closest {
+            self.mount(entity);
+        }
+    }
+
     /// Execute a single client tick, handle input and update the game state by the given duration
     #[allow(dead_code)]
     pub fn tick(&mut self, input: Input, dt: Duration) -> Result<Vec<Event>, Error> {
@@ -148,13 +306,38 @@ impl Client {
             println!("Chunk at {:?}", k);
         });
 
-        self.state.write_component(self.player, comp::Control {
+        let control = comp::Control {
             move_dir: input.move_dir,
-        });
+            gait: input.gait,
+            jump: input.jump,
+            glide: input.glide,
+            attack: input.attack,
+        };
+        self.state.write_component(self.player, control);
+
+        // Mounts are moved by the server, so it needs to know how the player is steering
+        if self.is_riding() {
+            self.postbox.send_message(ClientMsg::RiderControl(control));
+        }
 
         // Tick the client's LocalState (step 3)
         self.state.tick(dt);
 
+        // Let the server know about any attack the player just started, since hits are resolved
+        // on the server
+        let attack_time = self.state
+            .read_storage::<comp::Attacking>()
+            .get(self.player)
+            .map(|attacking| attacking.time);
+        if let Some(time) = attack_time {
+            // A finished attack can be replaced by a new one within a single tick, so a timer that
+            // went backwards means a new attack too
+            if self.last_attack_time.map(|last| time < last).unwrap_or(true) {
+                self.postbox.send_message(ClientMsg::Attack);
+            }
+        }
+        self.last_attack_time = attack_time;
+
         // Update the server about the player's physics attributes
         match (
             self.state.read_storage().get(self.player).cloned(),
@@ -216,10 +399,26 @@ impl Client {
             for msg in new_msgs {
                 match msg {
                     ServerMsg::Handshake { .. } => return Err(Error::ServerWentMad),
+                    ServerMsg::CharacterList(characters) => self.character_list = Some(characters),
+                    ServerMsg::CharacterActionError(err) => frontend_events.push(Event::CharacterActionError(err)),
+                    ServerMsg::CharacterSelected => frontend_events.push(Event::CharacterSelected),
                     ServerMsg::Shutdown => return Err(Error::ServerShutdown),
                     ServerMsg::Ping => self.postbox.send_message(ClientMsg::Pong),
                     ServerMsg::Pong => {},
                     ServerMsg::Chat(msg) => frontend_events.push(Event::Chat(msg)),
+                    ServerMsg::Damage { amount, source } => frontend_events.push(Event::Damage {
+                        amount,
+                        source: source.and_then(|uid| self.state.ecs().entity_from_uid(uid)),
+                    }),
+                    ServerMsg::Death { killer } => frontend_events.push(Event::Death {
+                        killer: killer.and_then(|uid| self.state.ecs().entity_from_uid(uid)),
+                    }),
+                    ServerMsg::TimeOfDay { time_of_day, day_length } => {
+                        self.state.set_time_of_day(time_of_day);
+                        self.state.set_day_length(day_length);
+                    },
+                    ServerMsg::Weather(cells) => self.state.weather_mut().apply_compressed(cells),
+                    ServerMsg::InventoryUpdate(inventory) => self.state.write_component(self.player, inventory),
                     ServerMsg::SetPlayerEntity(uid) => self.player = self.state.ecs().entity_from_uid(uid).unwrap(), // TODO: Don't unwrap here!
                     ServerMsg::EcsSync(sync_package) => self.state.ecs_mut().sync_with_package(sync_package),
                     ServerMsg::EntityPhysics { entity, pos, vel, dir } => match self.state.ecs().entity_from_uid(entity) {
@@ -236,10 +435,24 @@ impl Client {
                         },
                         None => {},
                     },
-                    ServerMsg::TerrainChunkUpdate { key, chunk } => {
+                    ServerMsg::TerrainChunkUpdate { key, version, chunk } => {
                         self.state.insert_chunk(key, *chunk);
+                        self.chunk_versions.insert(key, version);
                         self.pending_chunks.remove(&key);
                     },
+                    ServerMsg::TerrainChunkDelta { key, base_version, delta } => {
+                        let applied = self.chunk_versions.get(&key) == Some(&base_version)
+                            && self.state.apply_chunk_delta(key, &delta).is_ok();
+
+                        if applied {
+                            self.chunk_versions.insert(key, base_version + 1);
+                        } else if !self.pending_chunks.contains(&key) {
+                            // We've missed an update (or don't have the chunk), so ask for the
+                            // whole chunk again
+                            self.postbox.send_message(ClientMsg::TerrainChunkRequest { key });
+                            self.pending_chunks.insert(key);
+                        }
+                    },
                 }
             }
         } else if let Some(err) = self.postbox.error() {
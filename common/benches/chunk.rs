@@ -0,0 +1,74 @@
+#![feature(test)]
+
+extern crate test;
+
+use test::{Bencher, black_box};
+use vek::*;
+use veloren_common::{
+    terrain::{Block, TerrainChunk, TerrainChunkMeta, TerrainMap},
+    vol::{Vox, ReadVol, WriteVol, SampleVol},
+};
+
+/// Build a chunk that looks roughly like generated terrain: stone below, a layer of sand, air above
+fn terrain_chunk() -> TerrainChunk {
+    let mut chunk = TerrainChunk::filled(Block::empty(), TerrainChunkMeta::void());
+    let stone = Block::new(1, Rgb::new(200, 220, 255));
+    let sand = Block::new(3, Rgb::new(180, 150, 50));
+    for x in 0..32 {
+        for y in 0..32 {
+            let height = 12 + (x + y) % 8;
+            for z in 0..height {
+                chunk.set(Vec3::new(x, y, z), if z == height - 1 { sand } else { stone }).unwrap();
+            }
+        }
+    }
+    chunk
+}
+
+#[bench]
+fn get_uniform(b: &mut Bencher) {
+    let chunk = TerrainChunk::filled(Block::empty(), TerrainChunkMeta::void());
+    b.iter(|| {
+        for pos in (0..32).map(|i| Vec3::new(i, 31 - i, i)) {
+            black_box(chunk.get(pos).unwrap());
+        }
+    });
+}
+
+#[bench]
+fn get_mixed(b: &mut Bencher) {
+    let chunk = terrain_chunk();
+    b.iter(|| {
+        for pos in (0..32).map(|i| Vec3::new(i, 31 - i, i)) {
+            black_box(chunk.get(pos).unwrap());
+        }
+    });
+}
+
+#[bench]
+fn set_mixed(b: &mut Bencher) {
+    let mut chunk = terrain_chunk();
+    let block = Block::new(2, Rgb::new(50, 255, 0));
+    b.iter(|| {
+        for pos in (0..32).map(|i| Vec3::new(i, 31 - i, i)) {
+            chunk.set(pos, block).unwrap();
+        }
+    });
+}
+
+#[bench]
+fn sample_chunk_with_borders(b: &mut Bencher) {
+    let mut map = TerrainMap::new();
+    for x in -1..2 {
+        for y in -1..2 {
+            for z in -1..2 {
+                map.insert(Vec3::new(x, y, z), terrain_chunk());
+            }
+        }
+    }
+    let aabb = Aabb {
+        min: Vec3::broadcast(-1),
+        max: Vec3::broadcast(33),
+    };
+    b.iter(|| black_box(map.sample(aabb).unwrap()));
+}
@@ -1,50 +1,154 @@
 // Standard
 use std::{
+    collections::VecDeque,
     thread,
-    time::{Duration, SystemTime},
+    time::{Duration, Instant},
 };
 
 const CLOCK_SMOOTHING: f64 = 0.9;
+// The number of recent ticks that jitter statistics are computed over
+const STATS_WINDOW: usize = 100;
+
+/// Statistics about the timing of recent ticks.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ClockStats {
+    /// The average number of ticks per second.
+    pub tps: f64,
+    /// The average duration of a tick.
+    pub avg_delta: Duration,
+    /// The standard deviation of the tick duration.
+    pub jitter: Duration,
+    /// The longest tick duration.
+    pub max_delta: Duration,
+}
 
 pub struct Clock {
-    last_sys_time: SystemTime,
+    last_instant: Instant,
+    // The time at which the next tick is scheduled to end
+    next_target: Option<Instant>,
     last_delta: Option<Duration>,
-    running_tps_average: f64,
+    running_avg_delta: f64,
+    recent_deltas: VecDeque<Duration>,
+    overruns: u64,
 }
 
 impl Clock {
     pub fn new() -> Self {
         Self {
-            last_sys_time: SystemTime::now(),
+            last_instant: Instant::now(),
+            next_target: None,
             last_delta: None,
-            running_tps_average: 0.0,
+            running_avg_delta: 0.0,
+            recent_deltas: VecDeque::with_capacity(STATS_WINDOW),
+            overruns: 0,
         }
     }
 
-    pub fn get_tps(&self) -> f64 { self.running_tps_average }
+    /// Get the smoothed number of ticks per second.
+    pub fn get_tps(&self) -> f64 {
+        if self.running_avg_delta > 0.0 {
+            1.0 / self.running_avg_delta
+        } else {
+            0.0
+        }
+    }
 
     pub fn get_last_delta(&self) -> Duration { self.last_delta.unwrap_or(Duration::new(0, 0)) }
 
-    pub fn get_avg_delta(&self) -> Duration { Duration::from_secs_f64(self.running_tps_average) }
+    /// Get the smoothed duration of a tick.
+    pub fn get_avg_delta(&self) -> Duration { Duration::from_secs_f64(self.running_avg_delta) }
 
-    pub fn tick(&mut self, tgt: Duration) {
-        let delta = SystemTime::now()
-            .duration_since(self.last_sys_time)
-            .expect("Time went backwards!");
+    /// Get the number of ticks that took longer than their target duration, meaning that there
+    /// was no time left to sleep.
+    pub fn get_overruns(&self) -> u64 { self.overruns }
+
+    /// Get statistics about the timing of recent ticks.
+    pub fn get_stats(&self) -> ClockStats {
+        if self.recent_deltas.is_empty() {
+            return ClockStats::default();
+        }
+
+        let secs = self.recent_deltas.iter().map(|d| d.as_secs_f64()).collect::<Vec<_>>();
+        let mean = secs.iter().sum::<f64>() / secs.len() as f64;
+        let variance = secs.iter().map(|s| (s - mean) * (s - mean)).sum::<f64>() / secs.len() as f64;
 
-        // Attempt to sleep to fill the gap
-        if let Some(sleep_dur) = tgt.checked_sub(delta) {
-            thread::sleep(sleep_dur);
+        ClockStats {
+            tps: if mean > 0.0 { 1.0 / mean } else { 0.0 },
+            avg_delta: Duration::from_secs_f64(mean),
+            jitter: Duration::from_secs_f64(variance.sqrt()),
+            max_delta: self.recent_deltas.iter().max().cloned().unwrap_or_default(),
         }
+    }
+
+    /// Wait until the next tick is due, `tgt` after the previous one was due.
+    ///
+    /// Ticks are scheduled against absolute target times so that small oversleeps don't
+    /// accumulate into drift. If a tick overruns its target by more than a whole tick, the
+    /// schedule is reset rather than trying to catch up with a burst of short ticks.
+    pub fn tick(&mut self, tgt: Duration) {
+        let now = Instant::now();
+        let target = self.next_target.unwrap_or(self.last_instant) + tgt;
+
+        let next_target = if now < target {
+            // Attempt to sleep to fill the gap
+            thread::sleep(target - now);
+            target
+        } else {
+            self.overruns += 1;
+            if now - target > tgt {
+                now
+            } else {
+                target
+            }
+        };
 
-        let delta = SystemTime::now()
-            .duration_since(self.last_sys_time)
-            .expect("Time went backwards!");
+        let now = Instant::now();
+        let delta = now - self.last_instant;
 
-        self.last_sys_time = SystemTime::now();
+        self.last_instant = now;
+        self.next_target = Some(next_target);
         self.last_delta = Some(delta);
-        self.running_tps_average =
-            CLOCK_SMOOTHING * self.running_tps_average +
-            (1.0 - CLOCK_SMOOTHING) * delta.as_secs_f64();
+        self.running_avg_delta = if self.running_avg_delta == 0.0 {
+            delta.as_secs_f64()
+        } else {
+            CLOCK_SMOOTHING * self.running_avg_delta +
+            (1.0 - CLOCK_SMOOTHING) * delta.as_secs_f64()
+        };
+
+        if self.recent_deltas.len() >= STATS_WINDOW {
+            self.recent_deltas.pop_front();
+        }
+        self.recent_deltas.push_back(delta);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_at_target_rate() {
+        let mut clock = Clock::new();
+        for _ in 0..20 {
+            clock.tick(Duration::from_millis(5));
+        }
+
+        let stats = clock.get_stats();
+        // Sleeping may overshoot, but the schedule should never run faster than the target
+        assert!(stats.tps <= 210.0);
+        assert!(clock.get_tps() <= 210.0);
+        assert!(stats.max_delta >= stats.avg_delta);
+    }
+
+    #[test]
+    fn counts_overruns() {
+        let mut clock = Clock::new();
+        clock.tick(Duration::from_millis(1));
+        let overruns = clock.get_overruns();
+
+        thread::sleep(Duration::from_millis(10));
+        clock.tick(Duration::from_millis(1));
+
+        assert_eq!(clock.get_overruns(), overruns + 1);
     }
 }
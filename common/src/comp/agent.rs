@@ -1,24 +1,95 @@
-use specs::{Component, VecStorage};
+use specs::{Component, Entity as EcsEntity, VecStorage};
 use vek::*;
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
-pub enum Agent {
-    Wanderer(Vec2<f32>),
+/// How an agent reacts to the entities around it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Temperament {
+    /// Keeps to itself, only running away when badly hurt
+    Passive,
+    /// Runs away from any player that comes into sight
+    Timid,
+    /// Attacks any player that comes into sight
+    Hostile,
+    /// Follows the given entity around
+    Companion(EcsEntity),
+}
+
+/// What an agent is currently doing.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AgentState {
+    /// Standing around until the given time
+    Idle { until: f64 },
+    /// Walking to a spot near home
+    Wander { dest: Vec2<f32> },
+    Follow { target: EcsEntity },
+    Flee { from: EcsEntity },
+    Attack { target: EcsEntity },
+}
+
+/// An NPC controlled by the server. Agents decide what to do based on their temperament and what
+/// they can perceive, and steer themselves through their `Control` component.
+#[derive(Copy, Clone, Debug)]
+pub struct Agent {
+    pub temperament: Temperament,
+    /// The point the agent wanders around
+    pub home: Vec2<f32>,
+    /// How far from home the agent wanders (and how far it chases targets)
+    pub home_radius: f32,
+    /// How far away the agent notices other entities
+    pub sight_radius: f32,
+    pub state: AgentState,
+}
+
+impl Agent {
+    pub fn new(temperament: Temperament, home: Vec2<f32>) -> Self {
+        Self {
+            temperament,
+            home,
+            home_radius: 16.0,
+            sight_radius: 12.0,
+            state: AgentState::Idle { until: 0.0 },
+        }
+    }
+
+    pub fn with_home_radius(mut self, home_radius: f32) -> Self {
+        self.home_radius = home_radius;
+        self
+    }
+
+    pub fn with_sight_radius(mut self, sight_radius: f32) -> Self {
+        self.sight_radius = sight_radius;
+        self
+    }
 }
 
 impl Component for Agent {
     type Storage = VecStorage<Self>;
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Gait {
+    Walk,
+    Run,
+    Sprint,
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct Control {
     pub move_dir: Vec2<f32>,
+    pub gait: Gait,
+    pub jump: bool,
+    pub glide: bool,
+    pub attack: bool,
 }
 
 impl Default for Control {
     fn default() -> Self {
         Self {
             move_dir: Vec2::zero(),
+            gait: Gait::Run,
+            jump: false,
+            glide: false,
+            attack: false,
         }
     }
 }
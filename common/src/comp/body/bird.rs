@@ -0,0 +1,17 @@
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Species {
+    Crow,
+    Chicken,
+}
+
+/// The body of a two-legged, winged creature.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Body {
+    pub species: Species,
+}
+
+impl Body {
+    pub fn new(species: Species) -> Self {
+        Self { species }
+    }
+}
@@ -0,0 +1,43 @@
+pub mod bird;
+pub mod quadruped;
+
+use specs::{Component, FlaggedStorage, VecStorage};
+use vek::*;
+use super::character::Character;
+
+/// The physical form of a creature, which determines how it's displayed and animated.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Body {
+    Humanoid(Character),
+    Quadruped(quadruped::Body),
+    Bird(bird::Body),
+}
+
+impl Body {
+    /// The appearance of a humanoid body, or `None` for other kinds of creature.
+    pub fn humanoid(&self) -> Option<&Character> {
+        match self {
+            Body::Humanoid(character) => Some(character),
+            _ => None,
+        }
+    }
+
+    /// Where a rider sits relative to the body's position when it faces along the x axis, or
+    /// `None` if the body can't be ridden.
+    pub fn mount_offset(&self) -> Option<Vec3<f32>> {
+        match self {
+            Body::Quadruped(body) if body.is_tameable() => Some(quadruped::SADDLE_OFFSET),
+            _ => None,
+        }
+    }
+}
+
+impl From<Character> for Body {
+    fn from(character: Character) -> Self {
+        Body::Humanoid(character)
+    }
+}
+
+impl Component for Body {
+    type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+}
@@ -0,0 +1,42 @@
+use vek::*;
+
+/// Quadruped skeletons are modelled in units of `1 / SCALE` blocks.
+pub const SCALE: f32 = 11.0;
+/// The height of a standing quadruped's torso bone above its feet, in model units.
+pub const TORSO_HEIGHT: f32 = 9.0;
+/// The size of a quadruped's torso, in model units.
+pub const TORSO_SIZE: Vec3<u32> = Vec3 { x: 14, y: 8, z: 7 };
+/// Where a rider sits relative to a quadruped's position when it faces along the x axis: on top
+/// of its torso.
+pub const SADDLE_OFFSET: Vec3<f32> = Vec3 {
+    x: 0.0,
+    y: 0.0,
+    z: (TORSO_HEIGHT + TORSO_SIZE.z as f32 / 2.0) / SCALE,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Species {
+    Wolf,
+    Boar,
+    Pig,
+}
+
+/// The body of a four-legged creature.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Body {
+    pub species: Species,
+}
+
+impl Body {
+    pub fn new(species: Species) -> Self {
+        Self { species }
+    }
+
+    /// Whether creatures with this body can be ridden, and so tamed, by players.
+    pub fn is_tameable(&self) -> bool {
+        match self.species {
+            Species::Wolf | Species::Boar => true,
+            Species::Pig => false,
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use specs::{Component, VecStorage, FlaggedStorage};
 use vek::*;
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Race {
     Danari,
     Dwarf,
@@ -11,7 +11,7 @@ pub enum Race {
     Undead,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Gender {
     Female,
     Male,
@@ -28,18 +28,124 @@ pub struct AnimationHistory {
 pub enum Animation {
     Idle,
     Run,
+    Jump,
+    Glide,
+    Attack,
+    Death,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+// The parts a character's figure is assembled from. The first variant of each is what new
+// characters wear, the others are provided by armour items (see `Armor`).
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Head {
+    Bare,
+    LeatherCap,
+    IronHelm,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Chest {
+    Shirt,
+    LeatherVest,
+    IronChestplate,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Belt {
+    Cloth,
+    Leather,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Pants {
+    Cloth,
+    Leather,
+    IronGreaves,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Arms {
+    Bare,
+    LeatherGloves,
+    IronGauntlets,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Feet {
+    Shoes,
+    LeatherBoots,
+    IronBoots,
+}
+
+/// The part of a character's figure that an armour item replaces when it's worn.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Armor {
+    Head(Head),
+    Chest(Chest),
+    Belt(Belt),
+    Pants(Pants),
+    Arms(Arms),
+    Feet(Feet),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Weapon {
+    None,
+    Daggers,
+    SwordShield,
+    Sword,
+    Axe,
+    Hammer,
+    Bow,
+    Staff,
+}
+
+/// The number of hair styles to choose from.
+pub const HAIR_STYLE_COUNT: u8 = 4;
+
+// Characters refer to colours by their index in these palettes, which keeps the choices to ones
+// that fit the art style (and makes them trivial to validate)
+pub const HAIR_COLORS: [Rgb<u8>; 6] = [
+    Rgb { r: 40, g: 30, b: 25 },
+    Rgb { r: 95, g: 60, b: 35 },
+    Rgb { r: 180, g: 130, b: 70 },
+    Rgb { r: 230, g: 200, b: 120 },
+    Rgb { r: 150, g: 50, b: 30 },
+    Rgb { r: 200, g: 200, b: 200 },
+];
+pub const SKIN_COLORS: [Rgb<u8>; 6] = [
+    Rgb { r: 255, g: 220, b: 190 },
+    Rgb { r: 235, g: 190, b: 150 },
+    Rgb { r: 200, g: 150, b: 110 },
+    Rgb { r: 150, g: 100, b: 70 },
+    Rgb { r: 100, g: 65, b: 45 },
+    Rgb { r: 60, g: 40, b: 30 },
+];
+pub const EYE_COLORS: [Rgb<u8>; 4] = [
+    Rgb { r: 70, g: 45, b: 25 },
+    Rgb { r: 50, g: 100, b: 160 },
+    Rgb { r: 60, g: 130, b: 70 },
+    Rgb { r: 110, g: 110, b: 110 },
+];
+
+/// The appearance of a character. Characters that look the same share a figure model, so this
+/// is also used to key the client's model cache.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Character {
-    race: Race,
-    gender: Gender,
-    head: (),
-    chest: (),
-    belt: (),
-    arms: (),
-    feet: (),
-    
+    pub race: Race,
+    pub gender: Gender,
+    pub head: Head,
+    pub chest: Chest,
+    pub belt: Belt,
+    pub pants: Pants,
+    pub arms: Arms,
+    pub feet: Feet,
+    pub weapon: Weapon,
+    pub hair_style: u8,
+    pub hair_color: u8,
+    pub skin_color: u8,
+    pub eye_color: u8,
 }
 
 impl Character {
@@ -48,19 +154,82 @@ impl Character {
         Self {
             race: Race::Human,
             gender: Gender::Unspecified,
-            head: (),
-            chest: (),
-            belt: (),
-            arms: (),
-            feet: (),
+            head: Head::Bare,
+            chest: Chest::Shirt,
+            belt: Belt::Cloth,
+            pants: Pants::Cloth,
+            arms: Arms::Bare,
+            feet: Feet::Shoes,
+            weapon: Weapon::Sword,
+            hair_style: 0,
+            hair_color: 0,
+            skin_color: 0,
+            eye_color: 0,
         }
     }
-}
 
-impl Component for Character {
-    type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+    /// Create a new character with the given race, gender and starting weapon, default features
+    /// and starting clothes.
+    pub fn new(race: Race, gender: Gender, weapon: Weapon) -> Self {
+        Self {
+            race,
+            gender,
+            weapon,
+            ..Self::test()
+        }
+    }
+
+    /// Check that a new character only uses features that actually exist and wears nothing but
+    /// starting clothes. Characters received from clients must be validated before they're used.
+    pub fn is_valid(&self) -> bool {
+        let starting = Self::test();
+        self.head == starting.head &&
+        self.chest == starting.chest &&
+        self.belt == starting.belt &&
+        self.pants == starting.pants &&
+        self.arms == starting.arms &&
+        self.feet == starting.feet &&
+        self.hair_style < HAIR_STYLE_COUNT &&
+        (self.hair_color as usize) < HAIR_COLORS.len() &&
+        (self.skin_color as usize) < SKIN_COLORS.len() &&
+        (self.eye_color as usize) < EYE_COLORS.len()
+    }
+
+    pub fn hair_rgb(&self) -> Rgb<u8> {
+        HAIR_COLORS[self.hair_color as usize % HAIR_COLORS.len()]
+    }
+
+    pub fn skin_rgb(&self) -> Rgb<u8> {
+        SKIN_COLORS[self.skin_color as usize % SKIN_COLORS.len()]
+    }
+
+    pub fn eye_rgb(&self) -> Rgb<u8> {
+        EYE_COLORS[self.eye_color as usize % EYE_COLORS.len()]
+    }
 }
 
 impl Component for AnimationHistory {
     type Storage = FlaggedStorage<Self, VecStorage<Self>>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn features_must_exist() {
+        let character = Character::new(Race::Elf, Gender::Female, Weapon::Bow);
+        assert!(character.is_valid());
+
+        assert!(!Character { hair_style: HAIR_STYLE_COUNT, ..character }.is_valid());
+        assert!(!Character { skin_color: SKIN_COLORS.len() as u8, ..character }.is_valid());
+        assert!(!Character { eye_color: 255, ..character }.is_valid());
+    }
+
+    #[test]
+    fn new_characters_wear_starting_clothes() {
+        let character = Character::new(Race::Dwarf, Gender::Male, Weapon::Axe);
+        assert!(!Character { chest: Chest::IronChestplate, ..character }.is_valid());
+        assert!(!Character { head: Head::LeatherCap, ..character }.is_valid());
+    }
+}
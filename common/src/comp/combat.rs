@@ -0,0 +1,17 @@
+use specs::{Component, VecStorage};
+
+/// How long an attack lasts. A new attack can't be started until the last one has finished.
+pub const ATTACK_DURATION: f32 = 0.5;
+
+/// Present on entities that are in the middle of an attack.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Attacking {
+    /// The time since the attack started
+    pub time: f32,
+    /// Whether the attack has been resolved against its targets yet
+    pub applied: bool,
+}
+
+impl Component for Attacking {
+    type Storage = VecStorage<Self>;
+}
@@ -0,0 +1,215 @@
+use specs::{Component, FlaggedStorage, VecStorage};
+use crate::item::{ItemRegistry, ItemStack};
+
+/// The number of slots in a new inventory.
+pub const DEFAULT_INVENTORY_SIZE: usize = 18;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InventoryError {
+    /// The slot index is outside of the inventory
+    NoSuchSlot,
+    /// The slot doesn't contain anything
+    EmptySlot,
+    /// There's no empty slot to put items in
+    Full,
+    /// A stack can't be split into the requested amounts
+    InvalidAmount,
+}
+
+/// The items carried by an entity. Only the server modifies inventories; clients receive a copy
+/// of their own.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+}
+
+impl Inventory {
+    pub fn new(size: usize) -> Self {
+        Self {
+            slots: vec![None; size],
+        }
+    }
+
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    pub fn get(&self, slot: usize) -> Option<&ItemStack> {
+        self.slots.get(slot).and_then(|stack| stack.as_ref())
+    }
+
+    /// Add items to the inventory, topping up existing stacks of the same item before using empty
+    /// slots. Returns whatever didn't fit.
+    pub fn insert(&mut self, mut stack: ItemStack, registry: &ItemRegistry) -> Option<ItemStack> {
+        let stack_size = registry.stack_size(stack.item);
+
+        for existing in self.slots.iter_mut().filter_map(|slot| slot.as_mut()) {
+            if stack.amount == 0 {
+                break;
+            }
+            if existing.item == stack.item && existing.amount < stack_size {
+                let moved = (stack_size - existing.amount).min(stack.amount);
+                existing.amount += moved;
+                stack.amount -= moved;
+            }
+        }
+
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if stack.amount == 0 {
+                break;
+            }
+            let moved = stack_size.min(stack.amount);
+            *slot = Some(ItemStack::new(stack.item, moved));
+            stack.amount -= moved;
+        }
+
+        if stack.amount > 0 {
+            Some(stack)
+        } else {
+            None
+        }
+    }
+
+    /// Take the whole stack out of a slot.
+    pub fn remove(&mut self, slot: usize) -> Result<ItemStack, InventoryError> {
+        self.slots
+            .get_mut(slot)
+            .ok_or(InventoryError::NoSuchSlot)?
+            .take()
+            .ok_or(InventoryError::EmptySlot)
+    }
+
+    /// Move the contents of one slot to another. Stacks of the same item are merged (as far as the
+    /// stack size allows), anything else is swapped.
+    pub fn move_slot(&mut self, from: usize, to: usize, registry: &ItemRegistry) -> Result<(), InventoryError> {
+        if from >= self.slots.len() || to >= self.slots.len() {
+            return Err(InventoryError::NoSuchSlot);
+        }
+        if from == to {
+            return Ok(());
+        }
+
+        match (self.slots[from], self.slots[to]) {
+            (None, _) => Err(InventoryError::EmptySlot),
+            (Some(mut src), Some(mut dst)) if src.item == dst.item => {
+                let moved = (registry.stack_size(dst.item).saturating_sub(dst.amount)).min(src.amount);
+                dst.amount += moved;
+                src.amount -= moved;
+                self.slots[to] = Some(dst);
+                self.slots[from] = if src.amount > 0 { Some(src) } else { None };
+                Ok(())
+            },
+            _ => {
+                self.slots.swap(from, to);
+                Ok(())
+            },
+        }
+    }
+
+    /// Move `amount` items from a stack into the first empty slot.
+    pub fn split(&mut self, slot: usize, amount: u32) -> Result<(), InventoryError> {
+        let stack = self
+            .slots
+            .get(slot)
+            .ok_or(InventoryError::NoSuchSlot)?
+            .ok_or(InventoryError::EmptySlot)?;
+        if amount == 0 || amount >= stack.amount {
+            return Err(InventoryError::InvalidAmount);
+        }
+
+        let empty = self
+            .slots
+            .iter()
+            .position(|slot| slot.is_none())
+            .ok_or(InventoryError::Full)?;
+
+        self.slots[empty] = Some(ItemStack::new(stack.item, amount));
+        self.slots[slot] = Some(ItemStack::new(stack.item, stack.amount - amount));
+        Ok(())
+    }
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new(DEFAULT_INVENTORY_SIZE)
+    }
+}
+
+impl Component for Inventory {
+    type Storage = VecStorage<Self>;
+}
+
+/// An item lying in the world, waiting to be picked up.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ItemDrop(pub ItemStack);
+
+impl Component for ItemDrop {
+    type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::{ItemDef, ItemId, ItemKind};
+
+    fn registry() -> ItemRegistry {
+        ItemRegistry::from_defs(vec![
+            ItemDef {
+                id: ItemId(1),
+                name: String::from("Sword"),
+                kind: ItemKind::Weapon,
+                stack_size: 1,
+                model: String::new(),
+                armor: None,
+            },
+            ItemDef {
+                id: ItemId(2),
+                name: String::from("Stone"),
+                kind: ItemKind::Material,
+                stack_size: 10,
+                model: String::new(),
+                armor: None,
+            },
+        ]).unwrap()
+    }
+
+    #[test]
+    fn insert_fills_stacks_then_empty_slots() {
+        let registry = registry();
+        let mut inv = Inventory::new(3);
+
+        assert_eq!(inv.insert(ItemStack::new(ItemId(2), 4), &registry), None);
+        assert_eq!(inv.insert(ItemStack::new(ItemId(1), 1), &registry), None);
+        assert_eq!(inv.insert(ItemStack::new(ItemId(2), 8), &registry), None);
+        assert_eq!(inv.get(0), Some(&ItemStack::new(ItemId(2), 10)));
+        assert_eq!(inv.get(2), Some(&ItemStack::new(ItemId(2), 2)));
+
+        // No room left for more swords
+        assert_eq!(
+            inv.insert(ItemStack::new(ItemId(1), 1), &registry),
+            Some(ItemStack::new(ItemId(1), 1)),
+        );
+    }
+
+    #[test]
+    fn move_merges_or_swaps() {
+        let registry = registry();
+        let mut inv = Inventory::new(3);
+        inv.insert(ItemStack::new(ItemId(2), 10), &registry);
+        inv.split(0, 3).unwrap();
+        assert_eq!(inv.get(0), Some(&ItemStack::new(ItemId(2), 7)));
+        assert_eq!(inv.get(1), Some(&ItemStack::new(ItemId(2), 3)));
+
+        inv.move_slot(1, 0, &registry).unwrap();
+        assert_eq!(inv.get(0), Some(&ItemStack::new(ItemId(2), 10)));
+        assert_eq!(inv.get(1), None);
+
+        inv.insert(ItemStack::new(ItemId(1), 1), &registry);
+        inv.move_slot(1, 0, &registry).unwrap();
+        assert_eq!(inv.get(0), Some(&ItemStack::new(ItemId(1), 1)));
+        assert_eq!(inv.get(1), Some(&ItemStack::new(ItemId(2), 10)));
+
+        assert_eq!(inv.move_slot(2, 0, &registry), Err(InventoryError::EmptySlot));
+        assert_eq!(inv.split(1, 10), Err(InventoryError::InvalidAmount));
+    }
+}
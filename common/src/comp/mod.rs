@@ -1,11 +1,23 @@
 pub mod agent;
+pub mod body;
 pub mod character;
+pub mod combat;
+pub mod inventory;
+pub mod mount;
+pub mod movement;
 pub mod player;
 pub mod phys;
+pub mod stats;
 
 // Reexports
-pub use agent::{Agent, Control};
+pub use agent::{Agent, AgentState, Control, Gait, Temperament};
+pub use body::Body;
+pub use movement::MovementStats;
+pub use mount::{Mount, Rider};
 pub use character::Character;
 pub use player::Player;
 pub use character::AnimationHistory;
 pub use character::Animation;
+pub use stats::{Stats, Dead, Damage, DamageQueue};
+pub use combat::Attacking;
+pub use inventory::{Inventory, InventoryError, ItemDrop};
@@ -0,0 +1,26 @@
+use specs::{Component, FlaggedStorage, VecStorage};
+
+/// How close a creature has to be for a player to climb onto it.
+pub const MOUNT_REACH: f32 = 3.0;
+
+/// Present on a creature while it's being ridden. Riders are referred to by their `Uid` so that
+/// the relationship survives being synced to clients.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Mount {
+    pub rider: u64,
+}
+
+impl Component for Mount {
+    type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+}
+
+/// Present on an entity while it's riding a creature. The rider's controls steer the mount, and
+/// the rider is held in place on the mount's back.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rider {
+    pub mount: u64,
+}
+
+impl Component for Rider {
+    type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+}
@@ -0,0 +1,41 @@
+use specs::{Component, VecStorage};
+
+/// Per-entity constants that determine how an entity moves in response to its `Control`.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct MovementStats {
+    /// Horizontal acceleration while on the ground
+    pub accel: f32,
+    /// Horizontal acceleration while in the air
+    pub air_accel: f32,
+    /// Horizontal acceleration while gliding
+    pub glide_accel: f32,
+    /// Non-linear horizontal deceleration factor
+    pub drag: f32,
+    /// Acceleration multiplier while walking
+    pub walk_factor: f32,
+    /// Acceleration multiplier while sprinting
+    pub sprint_factor: f32,
+    /// Upward speed given by a jump
+    pub jump_speed: f32,
+    /// The fastest an entity will fall while gliding
+    pub glide_fall_speed: f32,
+}
+
+impl Default for MovementStats {
+    fn default() -> Self {
+        Self {
+            accel: 2.0,
+            air_accel: 0.5,
+            glide_accel: 1.0,
+            drag: 0.03,
+            walk_factor: 0.4,
+            sprint_factor: 1.6,
+            jump_speed: 12.0,
+            glide_fall_speed: 2.0,
+        }
+    }
+}
+
+impl Component for MovementStats {
+    type Storage = VecStorage<Self>;
+}
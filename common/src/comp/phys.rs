@@ -36,3 +36,13 @@ pub struct ForceUpdate;
 impl Component for ForceUpdate {
     type Storage = NullStorage<Self>;
 }
+
+// OnGround
+
+/// A marker for entities that are currently standing on solid terrain.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OnGround;
+
+impl Component for OnGround {
+    type Storage = NullStorage<Self>;
+}
@@ -0,0 +1,68 @@
+use specs::{Component, Entity as EcsEntity, FlaggedStorage, VecStorage};
+
+/// The vital statistics of an entity.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub health: u32,
+    pub max_health: u32,
+    /// Health regained every second while alive
+    pub health_regen: u32,
+}
+
+impl Stats {
+    pub fn new(max_health: u32) -> Self {
+        Self {
+            health: max_health,
+            max_health,
+            health_regen: 1,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health == 0
+    }
+
+    /// Get the entity's health as a fraction of its maximum health.
+    pub fn health_fraction(&self) -> f32 {
+        if self.max_health > 0 {
+            self.health as f32 / self.max_health as f32
+        } else {
+            0.0
+        }
+    }
+
+    /// Reduce the entity's health by `amount`, returning true if this killed it.
+    pub fn damage(&mut self, amount: u32) -> bool {
+        let was_dead = self.is_dead();
+        self.health = self.health.saturating_sub(amount);
+        !was_dead && self.is_dead()
+    }
+}
+
+impl Component for Stats {
+    type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+}
+
+/// Present on entities that have died, recording when they did so. This is only used by the
+/// server.
+#[derive(Copy, Clone, Debug)]
+pub struct Dead {
+    pub time_of_death: f64,
+}
+
+impl Component for Dead {
+    type Storage = VecStorage<Self>;
+}
+
+/// A request to damage an entity, to be handled by the server at the end of the tick.
+#[derive(Copy, Clone, Debug)]
+pub struct Damage {
+    pub target: EcsEntity,
+    pub amount: u32,
+    /// The entity responsible for the damage, if any
+    pub source: Option<EcsEntity>,
+}
+
+/// A resource used to collect the damage dealt during a tick.
+#[derive(Default)]
+pub struct DamageQueue(pub Vec<Damage>);
@@ -0,0 +1,97 @@
+//! # Items
+//! Every kind of item is described by an `ItemDef`, loaded from `assets/common/items.ron` into an
+//! `ItemRegistry`. Everything else refers to items by their `ItemId`.
+
+// Standard
+use std::collections::HashMap;
+
+// Crate
+use crate::{assets, comp::character::Armor};
+
+pub const ITEMS_ASSET: &str = "/common/items.ron";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ItemId(pub u32);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItemKind {
+    Weapon,
+    Armor,
+    Consumable,
+    Material,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ItemDef {
+    pub id: ItemId,
+    pub name: String,
+    pub kind: ItemKind,
+    /// The most of this item that fits in a single inventory slot
+    pub stack_size: u32,
+    /// The path of the voxel model used to display the item
+    pub model: String,
+    /// The part of a character's figure the item replaces when worn, if it's armour
+    #[serde(default)]
+    pub armor: Option<Armor>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The item definitions asset couldn't be loaded
+    MissingAsset,
+    Parse(ron::de::Error),
+    DuplicateId(ItemId),
+}
+
+/// A number of identical items.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item: ItemId,
+    pub amount: u32,
+}
+
+impl ItemStack {
+    pub fn new(item: ItemId, amount: u32) -> Self {
+        Self { item, amount }
+    }
+}
+
+/// All known item definitions, indexed by id.
+#[derive(Clone, Debug, Default)]
+pub struct ItemRegistry {
+    items: HashMap<ItemId, ItemDef>,
+}
+
+impl ItemRegistry {
+    /// Load the item definitions from the game's assets.
+    pub fn load() -> Result<Self, Error> {
+        let bytes = assets::load(ITEMS_ASSET).map_err(|_| Error::MissingAsset)?;
+        let defs: Vec<ItemDef> = ron::de::from_bytes(&bytes).map_err(Error::Parse)?;
+        Self::from_defs(defs)
+    }
+
+    pub fn from_defs(defs: Vec<ItemDef>) -> Result<Self, Error> {
+        let mut items = HashMap::new();
+        for def in defs {
+            let id = def.id;
+            if items.insert(id, def).is_some() {
+                return Err(Error::DuplicateId(id));
+            }
+        }
+        Ok(Self { items })
+    }
+
+    pub fn get(&self, id: ItemId) -> Option<&ItemDef> {
+        self.items.get(&id)
+    }
+
+    /// Get the stack size of an item. Unknown items don't stack.
+    pub fn stack_size(&self, id: ItemId) -> u32 {
+        self.get(id).map(|def| def.stack_size.max(1)).unwrap_or(1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ItemDef> {
+        self.items.values()
+    }
+}
@@ -9,13 +9,18 @@ pub mod assets;
 pub mod clock;
 pub mod comp;
 pub mod figure;
+pub mod item;
 pub mod msg;
+pub mod path;
+pub mod ray;
+pub mod spawn;
 pub mod state;
 pub mod sys;
 pub mod terrain;
 pub mod util;
 pub mod volumes;
 pub mod vol;
+pub mod weather;
 // TODO: unignore the code here, for some reason it refuses to compile here while has no problems copy-pasted elsewhere
 /// The networking module containing high-level wrappers of `TcpListener` and `TcpStream` (`PostOffice` and `PostBox` respectively) and data types used by both the server and client
 /// # Examples
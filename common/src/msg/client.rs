@@ -1,12 +1,26 @@
 use vek::*;
-use crate::comp;
+use crate::{
+    comp,
+    terrain::Block,
+};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub enum ClientMsg {
     Connect {
         player: comp::Player,
-        character: Option<comp::Character>,
-        
+    },
+    /// Ask for the list of the account's characters
+    RequestCharacterList,
+    CreateCharacter {
+        name: String,
+        character: comp::Character,
+    },
+    DeleteCharacter {
+        name: String,
+    },
+    /// Enter the world with one of the account's characters
+    SelectCharacter {
+        name: String,
     },
     Ping,
     Pong,
@@ -17,8 +31,42 @@ pub enum ClientMsg {
         vel: comp::phys::Vel,
         dir: comp::phys::Dir,
     },
+    /// Start an attack in the direction the player is facing
+    Attack,
+    /// Move the contents of one inventory slot to another, merging or swapping stacks
+    MoveInventorySlot {
+        from: usize,
+        to: usize,
+    },
+    /// Split `amount` items off a stack into an empty inventory slot
+    SplitInventorySlot {
+        slot: usize,
+        amount: u32,
+    },
+    /// Drop the contents of an inventory slot into the world
+    DropInventorySlot {
+        slot: usize,
+    },
+    /// Pick up a dropped item
+    PickUp {
+        entity: u64,
+    },
+    /// Climb onto a creature to ride it
+    Mount {
+        entity: u64,
+    },
+    Dismount,
+    /// The player's movement input while riding, which steers their mount
+    RiderControl(comp::Control),
     TerrainChunkRequest {
         key: Vec3<i32>,
     },
+    SetBlock {
+        pos: Vec3<i32>,
+        block: Block,
+    },
+    RemoveBlock {
+        pos: Vec3<i32>,
+    },
     Disconnect,
 }
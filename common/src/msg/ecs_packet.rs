@@ -9,8 +9,12 @@ sphynx::sum_type! {
         Pos(comp::phys::Pos),
         Vel(comp::phys::Vel),
         Dir(comp::phys::Dir),
-        Character(comp::Character),
+        Body(comp::Body),
         Player(comp::Player),
+        Stats(comp::Stats),
+        ItemDrop(comp::ItemDrop),
+        Mount(comp::Mount),
+        Rider(comp::Rider),
     }
 }
 // Automatically derive From<T> for Phantom for each variant Phantom::T(PhantomData<T>)
@@ -20,8 +24,12 @@ sphynx::sum_type! {
         Pos(PhantomData<comp::phys::Pos>),
         Vel(PhantomData<comp::phys::Vel>),
         Dir(PhantomData<comp::phys::Dir>),
-        Character(PhantomData<comp::Character>),
+        Body(PhantomData<comp::Body>),
         Player(PhantomData<comp::Player>),
+        Stats(PhantomData<comp::Stats>),
+        ItemDrop(PhantomData<comp::ItemDrop>),
+        Mount(PhantomData<comp::Mount>),
+        Rider(PhantomData<comp::Rider>),
     }
 }
 impl sphynx::Packet for EcsPacket {
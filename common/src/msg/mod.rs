@@ -3,6 +3,6 @@ pub mod server;
 pub mod client;
 
 // Reexports
-pub use self::server::ServerMsg;
+pub use self::server::{ServerMsg, CharacterInfo};
 pub use self::client::ClientMsg;
 pub use self::ecs_packet::EcsPacket;
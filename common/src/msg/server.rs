@@ -1,20 +1,52 @@
 use vek::*;
 use crate::{
     comp,
-    terrain::TerrainChunk,
+    terrain::{TerrainChunk, TerrainChunkDelta},
+    weather::CompressedWeather,
 };
 use super::EcsPacket;
 
+/// An entry in the list of an account's characters.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CharacterInfo {
+    pub name: String,
+    pub character: comp::Character,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub enum ServerMsg {
     Handshake {
         ecs_state: sphynx::StatePackage<EcsPacket>,
         player_entity: u64,
+        time_of_day: f64,
+        day_length: f64,
     },
+    /// The characters belonging to the recipient's account
+    CharacterList(Vec<CharacterInfo>),
+    /// A request to create, delete or select a character was refused
+    CharacterActionError(String),
+    /// The recipient's selected character has entered the world
+    CharacterSelected,
     Shutdown,
     Ping,
     Pong,
     Chat(String),
+    /// The recipient's character was damaged
+    Damage {
+        amount: u32,
+        source: Option<u64>,
+    },
+    /// The recipient's character died
+    Death {
+        killer: Option<u64>,
+    },
+    /// The server's time of day, sent periodically to correct any drift in the client's clock
+    TimeOfDay {
+        time_of_day: f64,
+        day_length: f64,
+    },
+    /// The recipient's inventory, sent whenever it changes
+    InventoryUpdate(comp::Inventory),
     SetPlayerEntity(u64),
     EcsSync(sphynx::SyncPackage<EcsPacket>),
     EntityPhysics {
@@ -27,8 +59,18 @@ pub enum ServerMsg {
         entity: u64,
         animation_history: comp::AnimationHistory,
     },
+    /// The weather of every simulated weather cell
+    Weather(Vec<(Vec2<i32>, CompressedWeather)>),
     TerrainChunkUpdate {
         key: Vec3<i32>,
+        version: u64,
         chunk: Box<TerrainChunk>,
     },
+    /// Changes to a chunk, relative to version `base_version` (the resulting chunk has version
+    /// `base_version + 1`)
+    TerrainChunkDelta {
+        key: Vec3<i32>,
+        base_version: u64,
+        delta: TerrainChunkDelta,
+    },
 }
@@ -0,0 +1,526 @@
+// Standard
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+};
+
+// Library
+use specs::Entity as EcsEntity;
+use vek::*;
+
+// Crate
+use crate::terrain::TerrainMap;
+
+/// The number of nodes the `Pathfinder` expands per update, shared between all pending searches.
+pub const DEFAULT_BUDGET: usize = 2048;
+
+// Searches that expand more nodes than this give up
+const MAX_SEARCH_NODES: usize = 4096;
+// Each pending search gets at least this many nodes per update, so that they all make progress
+const MIN_SHARE: usize = 64;
+// How far the ground below a goal is searched for
+const GOAL_SNAP_HEIGHT: i32 = 8;
+// Goals that move less than this many blocks keep their existing path
+const REPATH_DISTANCE: i32 = 2;
+// Waypoints within this horizontal distance have been reached
+const WAYPOINT_REACH: f32 = 0.6;
+// Agents that stray further than this from their next waypoint look for a new path
+const MAX_STRAY: f32 = 4.0;
+// The extra cost of stepping up or down a block
+const STEP_COST: f32 = 0.5;
+
+/// Whether a creature can stand at `pos`: the block below it is solid and it has two blocks of
+/// air to stand in.
+pub fn is_walkable<F: Fn(Vec3<i32>) -> bool>(is_solid: &F, pos: Vec3<i32>) -> bool {
+    is_solid(pos - Vec3::unit_z()) && !is_solid(pos) && !is_solid(pos + Vec3::unit_z())
+}
+
+/// The walkable cells that can be reached from `pos` in a single step, along with the cost of
+/// getting to each of them. Creatures can step up or down a single block, and may move
+/// diagonally as long as they don't cut across a solid corner.
+fn neighbours<F: Fn(Vec3<i32>) -> bool>(is_solid: &F, pos: Vec3<i32>) -> Vec<(Vec3<i32>, f32)> {
+    let clear = |pos: Vec3<i32>, height: i32| (0..height).all(|z| !is_solid(pos + Vec3::unit_z() * z));
+
+    let mut neighbours = Vec::with_capacity(8);
+    for dir in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)].iter() {
+        let dir = Vec3::new(dir.0, dir.1, 0);
+        for dz in [0, 1, -1].iter() {
+            let next = pos + dir + Vec3::unit_z() * *dz;
+            // Stepping up needs headroom above us, stepping down needs headroom above the target
+            let headroom = match dz {
+                1 => clear(pos, 3),
+                -1 => clear(next, 3),
+                _ => true,
+            };
+            if !headroom || !is_walkable(is_solid, next) {
+                continue;
+            }
+
+            let diagonal = dir.x != 0 && dir.y != 0;
+            let bottom = pos.z.max(next.z);
+            if diagonal
+                && !(clear(Vec3::new(pos.x + dir.x, pos.y, bottom), 2)
+                    && clear(Vec3::new(pos.x, pos.y + dir.y, bottom), 2))
+            {
+                continue;
+            }
+
+            let cost = if diagonal { std::f32::consts::SQRT_2 } else { 1.0 }
+                + if *dz == 0 { 0.0 } else { STEP_COST };
+            neighbours.push((next, cost));
+            break;
+        }
+    }
+    neighbours
+}
+
+fn heuristic(a: Vec3<i32>, b: Vec3<i32>) -> f32 {
+    let d = (a - b).map(|e| e.abs() as f32);
+    // Octile distance across the ground, plus the cost of the steps needed to change height
+    d.x.max(d.y) + (std::f32::consts::SQRT_2 - 1.0) * d.x.min(d.y) + d.z * STEP_COST
+}
+
+/// A sequence of walkable cells leading to a goal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path {
+    nodes: Vec<Vec3<i32>>,
+}
+
+impl Path {
+    /// The cells along the path, excluding the cell it starts in.
+    pub fn nodes(&self) -> &[Vec3<i32>] {
+        &self.nodes
+    }
+
+    /// The keys of the terrain chunks containing any cell that `neighbours` checks to step onto
+    /// the path's nodes: the ground below them, the headroom above them and the corners cut by
+    /// diagonal steps. All of those lie within a block horizontally and from a block below to two
+    /// blocks above each node.
+    fn chunk_keys(&self) -> HashSet<Vec3<i32>> {
+        let mut keys = HashSet::new();
+        for node in &self.nodes {
+            insert_chunk_keys(&mut keys, *node - Vec3::new(1, 1, 1), *node + Vec3::new(1, 1, 2));
+        }
+        keys
+    }
+}
+
+/// Add the keys of the terrain chunks overlapping the box from `min` to `max` (inclusive).
+fn insert_chunk_keys(keys: &mut HashSet<Vec3<i32>>, min: Vec3<i32>, max: Vec3<i32>) {
+    let size = TerrainMap::chunk_size();
+    let key = |pos: Vec3<i32>| pos.map2(size, |e, sz| e.div_euclid(sz as i32));
+
+    let (min, max) = (key(min), key(max));
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                keys.insert(Vec3::new(x, y, z));
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchStatus {
+    Pending,
+    Found(Path),
+    NotFound,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct OpenNode {
+    pos: Vec3<i32>,
+    // Estimated total cost of a path through this node
+    score: f32,
+}
+
+impl PartialEq for OpenNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.score == other.score
+    }
+}
+
+impl Eq for OpenNode {}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenNode {
+    // Reversed, so that `BinaryHeap` pops the node with the lowest score first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.score.partial_cmp(&self.score).unwrap_or(Ordering::Equal)
+    }
+}
+
+/// An A* search over walkable cells that can be run a few nodes at a time.
+#[derive(Clone, Debug)]
+pub struct Search {
+    start: Vec3<i32>,
+    goal: Vec3<i32>,
+    open: BinaryHeap<OpenNode>,
+    came_from: HashMap<Vec3<i32>, Vec3<i32>>,
+    costs: HashMap<Vec3<i32>, f32>,
+    expanded: usize,
+    // The keys of the chunks containing every cell the search has looked at
+    chunks: HashSet<Vec3<i32>>,
+}
+
+impl Search {
+    pub fn new(start: Vec3<i32>, goal: Vec3<i32>) -> Self {
+        let mut costs = HashMap::new();
+        costs.insert(start, 0.0);
+        let mut open = BinaryHeap::new();
+        open.push(OpenNode { pos: start, score: heuristic(start, goal) });
+
+        Self {
+            start,
+            goal,
+            open,
+            came_from: HashMap::new(),
+            costs,
+            expanded: 0,
+            chunks: HashSet::new(),
+        }
+    }
+
+    /// Expand up to `budget` nodes, subtracting the number actually expanded from it. The goal is
+    /// moved down onto the ground the first time the search is stepped, so goals in mid-air (like
+    /// a jumping target) can still be reached.
+    pub fn step<F: Fn(Vec3<i32>) -> bool>(&mut self, is_solid: &F, budget: &mut usize) -> SearchStatus {
+        if self.expanded == 0 {
+            let column = (self.goal - Vec3::unit_z() * GOAL_SNAP_HEIGHT, self.goal + Vec3::unit_z());
+            insert_chunk_keys(&mut self.chunks, column.0, column.1);
+            if let Some(goal) = (0..GOAL_SNAP_HEIGHT)
+                .map(|z| self.goal - Vec3::unit_z() * z)
+                .find(|pos| is_walkable(is_solid, *pos))
+            {
+                self.goal = goal;
+                self.open.clear();
+                self.open.push(OpenNode { pos: self.start, score: heuristic(self.start, goal) });
+            }
+        }
+
+        while *budget > 0 {
+            let node = match self.open.pop() {
+                Some(node) => node,
+                None => return SearchStatus::NotFound,
+            };
+            if node.pos == self.goal {
+                return SearchStatus::Found(self.reconstruct());
+            }
+            // Skip nodes that were queued again after a cheaper way to them was found
+            let cost = self.costs[&node.pos];
+            if node.score > cost + heuristic(node.pos, self.goal) + 0.001 {
+                continue;
+            }
+            if self.expanded >= MAX_SEARCH_NODES {
+                return SearchStatus::NotFound;
+            }
+
+            *budget -= 1;
+            self.expanded += 1;
+            // Stepping down checks the ground two blocks below the node, stepping up the headroom
+            // two blocks above it
+            insert_chunk_keys(&mut self.chunks, node.pos - Vec3::new(1, 1, 2), node.pos + Vec3::new(1, 1, 2));
+
+            for (next, step_cost) in neighbours(is_solid, node.pos) {
+                let next_cost = cost + step_cost;
+                if self.costs.get(&next).map(|c| next_cost < *c).unwrap_or(true) {
+                    self.costs.insert(next, next_cost);
+                    self.came_from.insert(next, node.pos);
+                    self.open.push(OpenNode { pos: next, score: next_cost + heuristic(next, self.goal) });
+                }
+            }
+        }
+
+        SearchStatus::Pending
+    }
+
+    fn reconstruct(&self) -> Path {
+        let mut nodes = vec![self.goal];
+        let mut pos = self.goal;
+        while let Some(prev) = self.came_from.get(&pos) {
+            if *prev == self.start {
+                break;
+            }
+            nodes.push(*prev);
+            pos = *prev;
+        }
+        nodes.reverse();
+        Path { nodes }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Route {
+    Searching(Search),
+    Found { path: Path, next: usize },
+    Failed,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    goal: Vec3<i32>,
+    route: Route,
+    chunks: HashSet<Vec3<i32>>,
+}
+
+/// Finds and caches paths for many entities, spreading the work of searching across ticks.
+/// Cached paths are thrown away when the terrain they pass through changes.
+#[derive(Clone, Debug, Default)]
+pub struct Pathfinder {
+    entries: HashMap<EcsEntity, Entry>,
+    queue: VecDeque<EcsEntity>,
+}
+
+impl Pathfinder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask for the way from `pos` to `goal` for the given entity, returning the next point to move
+    /// towards if a path is known. A new search is started when there's no path yet or the goal
+    /// has moved; until it finishes (or if there is no path) this returns `None`.
+    pub fn route(&mut self, entity: EcsEntity, pos: Vec3<f32>, goal: Vec3<f32>) -> Option<Vec3<f32>> {
+        let start = pos.map(|e| e.floor() as i32);
+        let goal = goal.map(|e| e.floor() as i32);
+
+        let stale = match self.entries.get(&entity) {
+            Some(entry) => {
+                let moved = (entry.goal - goal).map(|e| e.abs()).reduce_max() > REPATH_DISTANCE;
+                let strayed = match &entry.route {
+                    Route::Found { path, next } => path.nodes.get(*next)
+                        .map(|node| Vec2::<f32>::from(pos).distance(Vec2::<f32>::from(cell_centre(*node))) > MAX_STRAY)
+                        .unwrap_or(false),
+                    _ => false,
+                };
+                moved || strayed
+            },
+            None => true,
+        };
+
+        if stale {
+            let was_searching = match self.entries.get(&entity) {
+                Some(Entry { route: Route::Searching(_), .. }) => true,
+                _ => false,
+            };
+            self.entries.insert(entity, Entry {
+                goal,
+                route: Route::Searching(Search::new(start, goal)),
+                chunks: HashSet::new(),
+            });
+            if !was_searching {
+                self.queue.push_back(entity);
+            }
+        }
+
+        match &mut self.entries.get_mut(&entity)?.route {
+            Route::Found { path, next } => {
+                while let Some(node) = path.nodes.get(*next) {
+                    let centre = cell_centre(*node);
+                    if Vec2::<f32>::from(pos).distance(Vec2::<f32>::from(centre)) < WAYPOINT_REACH
+                        && (pos.z - centre.z).abs() < 1.5
+                    {
+                        *next += 1;
+                    } else {
+                        return Some(centre);
+                    }
+                }
+                None
+            },
+            _ => None,
+        }
+    }
+
+    /// Run pending searches, expanding at most `budget` nodes between them.
+    pub fn update<F: Fn(Vec3<i32>) -> bool>(&mut self, is_solid: &F, mut budget: usize) {
+        let share = (budget / self.queue.len().max(1)).max(MIN_SHARE);
+
+        for _ in 0..self.queue.len() {
+            if budget == 0 {
+                break;
+            }
+            let entity = match self.queue.pop_front() {
+                Some(entity) => entity,
+                None => break,
+            };
+            let entry = match self.entries.get_mut(&entity) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let search = match &mut entry.route {
+                Route::Searching(search) => search,
+                _ => continue,
+            };
+
+            let mut allowance = share.min(budget);
+            let status = search.step(is_solid, &mut allowance);
+            budget -= share.min(budget) - allowance;
+
+            match status {
+                SearchStatus::Pending => self.queue.push_back(entity),
+                SearchStatus::Found(path) => {
+                    entry.chunks = path.chunk_keys();
+                    entry.route = Route::Found { path, next: 0 };
+                },
+                SearchStatus::NotFound => {
+                    entry.chunks = std::mem::replace(&mut search.chunks, HashSet::new());
+                    entry.route = Route::Failed;
+                },
+            }
+        }
+    }
+
+    /// Throw away any paths through the chunk with the given key, along with failed searches that
+    /// looked at it, since the change may have opened up a way to their goal. Unfinished searches
+    /// carry on, seeing the new terrain from then on.
+    pub fn invalidate_chunk(&mut self, key: Vec3<i32>) {
+        self.entries.retain(|_, entry| match entry.route {
+            Route::Found { .. } | Route::Failed => !entry.chunks.contains(&key),
+            Route::Searching(_) => true,
+        });
+    }
+
+    /// Forget the paths of entities that don't match the predicate.
+    pub fn retain<P: FnMut(EcsEntity) -> bool>(&mut self, mut predicate: P) {
+        self.entries.retain(|entity, _| predicate(*entity));
+    }
+
+    /// Whether a search for the entity is still running.
+    pub fn is_searching(&self, entity: EcsEntity) -> bool {
+        match self.entries.get(&entity) {
+            Some(Entry { route: Route::Searching(_), .. }) => true,
+            _ => false,
+        }
+    }
+}
+
+fn cell_centre(pos: Vec3<i32>) -> Vec3<f32> {
+    pos.map(|e| e as f32) + Vec3::new(0.5, 0.5, 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{Builder, World};
+
+    // Flat ground at z = 0, with a one block step at x = 5 and a wall at x = 10 with a gap at y = 8
+    fn is_solid(pos: Vec3<i32>) -> bool {
+        pos.z < 0
+            || (pos.x >= 5 && pos.z == 0)
+            || (pos.x == 10 && pos.y != 8 && pos.z < 6)
+    }
+
+    fn search(start: Vec3<i32>, goal: Vec3<i32>) -> SearchStatus {
+        let mut budget = MAX_SEARCH_NODES + 1;
+        Search::new(start, goal).step(&is_solid, &mut budget)
+    }
+
+    #[test]
+    fn walkable_cells() {
+        assert!(is_walkable(&is_solid, Vec3::new(0, 0, 0)));
+        assert!(!is_walkable(&is_solid, Vec3::new(0, 0, 1)));
+        assert!(is_walkable(&is_solid, Vec3::new(6, 0, 1)));
+        assert!(!is_walkable(&is_solid, Vec3::new(10, 0, 1)));
+        assert!(is_walkable(&is_solid, Vec3::new(10, 8, 1)));
+    }
+
+    #[test]
+    fn steps_up_and_around_walls() {
+        let path = match search(Vec3::new(0, 0, 0), Vec3::new(12, 0, 1)) {
+            SearchStatus::Found(path) => path,
+            status => panic!("Expected a path, got {:?}", status),
+        };
+
+        let nodes = path.nodes();
+        assert_eq!(*nodes.last().unwrap(), Vec3::new(12, 0, 1));
+        assert!(nodes.iter().all(|node| is_walkable(&is_solid, *node)));
+        // The only way past the wall is through the gap
+        assert!(nodes.iter().any(|node| node.x == 10 && node.y == 8));
+        // No step is ever more than a block high
+        assert!(nodes.windows(2).all(|w| (w[1] - w[0]).map(|e| e.abs()).reduce_max() <= 1));
+    }
+
+    #[test]
+    fn cannot_climb_walls() {
+        let high_wall = |pos: Vec3<i32>| pos.z < 0 || (pos.x >= 5 && pos.z < 2);
+        let mut budget = MAX_SEARCH_NODES + 1;
+        let mut search = Search::new(Vec3::new(0, 0, 0), Vec3::new(8, 0, 2));
+        // The search space is unbounded, so it gives up after expanding too many nodes
+        assert_eq!(search.step(&high_wall, &mut budget), SearchStatus::NotFound);
+    }
+
+    #[test]
+    fn chunk_keys_cover_every_checked_cell() {
+        let size = TerrainMap::chunk_size().map(|e| e as i32);
+        // In the corner of a chunk, with its headroom in the chunk above
+        let path = Path { nodes: vec![Vec3::new(0, size.y - 1, size.z - 2)] };
+        let keys = path.chunk_keys();
+
+        for cell in &[
+            Vec3::new(0, size.y - 1, size.z - 3),
+            Vec3::new(0, size.y - 1, size.z),
+            Vec3::new(-1, size.y - 1, size.z - 2),
+            Vec3::new(0, size.y, size.z - 2),
+            Vec3::new(-1, size.y, size.z - 1),
+        ] {
+            assert!(keys.contains(&cell.map2(size, |e, sz| e.div_euclid(sz))));
+        }
+        assert!(!keys.contains(&Vec3::new(1, 0, 0)));
+    }
+
+    #[test]
+    fn failed_searches_are_only_retried_after_nearby_changes() {
+        let high_wall = |pos: Vec3<i32>| pos.z < 0 || (pos.x >= 5 && pos.z < 2);
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+        let (pos, goal) = (Vec3::new(0.5, 0.5, 0.0), Vec3::new(8.5, 0.5, 2.0));
+
+        let mut pathfinder = Pathfinder::new();
+        assert_eq!(pathfinder.route(entity, pos, goal), None);
+        while pathfinder.is_searching(entity) {
+            pathfinder.update(&high_wall, DEFAULT_BUDGET);
+        }
+
+        // Terrain far away from anywhere the search looked doesn't help
+        pathfinder.invalidate_chunk(Vec3::new(100, 100, 0));
+        assert_eq!(pathfinder.route(entity, pos, goal), None);
+        assert!(!pathfinder.is_searching(entity));
+
+        pathfinder.invalidate_chunk(Vec3::zero());
+        assert_eq!(pathfinder.route(entity, pos, goal), None);
+        assert!(pathfinder.is_searching(entity));
+    }
+
+    #[test]
+    fn searches_are_incremental_and_budgeted() {
+        let mut world = World::new();
+        let entities = (0..3).map(|_| world.create_entity().build()).collect::<Vec<_>>();
+
+        let mut pathfinder = Pathfinder::new();
+        for entity in &entities {
+            assert_eq!(pathfinder.route(*entity, Vec3::new(0.5, 0.5, 0.0), Vec3::new(12.5, 0.5, 1.0)), None);
+        }
+
+        // A tiny budget isn't enough for anybody to finish
+        pathfinder.update(&is_solid, 1);
+        assert!(entities.iter().all(|entity| pathfinder.is_searching(*entity)));
+
+        for _ in 0..100 {
+            pathfinder.update(&is_solid, DEFAULT_BUDGET);
+        }
+        for entity in &entities {
+            assert!(!pathfinder.is_searching(*entity));
+            let waypoint = pathfinder.route(*entity, Vec3::new(0.5, 0.5, 0.0), Vec3::new(12.5, 0.5, 1.0));
+            assert!(waypoint.unwrap().distance(Vec3::new(0.5, 0.5, 0.0)) < 2.0);
+        }
+
+        // Changing the terrain along the path throws it away
+        pathfinder.invalidate_chunk(Vec3::zero());
+        assert_eq!(pathfinder.route(entities[0], Vec3::new(0.5, 0.5, 0.0), Vec3::new(12.5, 0.5, 1.0)), None);
+        assert!(pathfinder.is_searching(entities[0]));
+    }
+}
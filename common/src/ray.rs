@@ -0,0 +1,270 @@
+// Library
+use vek::*;
+
+// Crate
+use crate::vol::{Vox, ReadVol};
+
+/// A single voxel visited by a ray.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RayStep {
+    /// The position of the voxel.
+    pub pos: Vec3<i32>,
+    /// The normal of the face through which the ray entered the voxel. This is zero for the voxel
+    /// that the ray starts in.
+    pub normal: Vec3<i32>,
+    /// The distance along the ray at which it entered the voxel.
+    pub dist: f32,
+    /// The point at which the ray entered the voxel.
+    pub point: Vec3<f32>,
+}
+
+/// An iterator over the voxel positions that a line segment passes through, in order, using a 3D
+/// DDA (Amanatides & Woo) traversal.
+pub struct RaySteps {
+    from: Vec3<f32>,
+    dir: Vec3<f32>,
+    max_dist: f32,
+    pos: [i32; 3],
+    step: [i32; 3],
+    t_max: [f32; 3],
+    t_delta: [f32; 3],
+    next: Option<RayStep>,
+}
+
+impl RaySteps {
+    pub fn new(from: Vec3<f32>, to: Vec3<f32>) -> Self {
+        let max_dist = from.distance(to);
+        let dir = if max_dist > 0.0 { (to - from) / max_dist } else { Vec3::zero() };
+
+        let start = from.map(|e| e.floor());
+        let step = dir.map(|e| if e > 0.0 { 1 } else if e < 0.0 { -1 } else { 0 });
+        // The distance along the ray at which the first voxel boundary is crossed on each axis
+        let t_max = (from - start).map2(dir, |offs, d| {
+            if d > 0.0 {
+                (1.0 - offs) / d
+            } else if d < 0.0 {
+                offs / -d
+            } else {
+                std::f32::INFINITY
+            }
+        });
+        // The distance along the ray between voxel boundaries on each axis
+        let t_delta = dir.map(|e| if e != 0.0 { 1.0 / e.abs() } else { std::f32::INFINITY });
+
+        Self {
+            from,
+            dir,
+            max_dist,
+            pos: start.map(|e| e as i32).into_array(),
+            step: step.into_array(),
+            t_max: t_max.into_array(),
+            t_delta: t_delta.into_array(),
+            next: Some(RayStep {
+                pos: start.map(|e| e as i32),
+                normal: Vec3::zero(),
+                dist: 0.0,
+                point: from,
+            }),
+        }
+    }
+}
+
+impl Iterator for RaySteps {
+    type Item = RayStep;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+
+        // Find the axis on which the next voxel boundary is crossed first
+        let axis = if self.t_max[0] < self.t_max[1] {
+            if self.t_max[0] < self.t_max[2] { 0 } else { 2 }
+        } else if self.t_max[1] < self.t_max[2] {
+            1
+        } else {
+            2
+        };
+
+        let dist = self.t_max[axis];
+        if dist <= self.max_dist {
+            self.pos[axis] += self.step[axis];
+            self.t_max[axis] += self.t_delta[axis];
+
+            let mut normal = [0; 3];
+            normal[axis] = -self.step[axis];
+
+            self.next = Some(RayStep {
+                pos: Vec3::from(self.pos),
+                normal: Vec3::from(normal),
+                dist,
+                point: self.from + self.dir * dist,
+            });
+        }
+
+        Some(current)
+    }
+}
+
+/// A ray cast through a volume, created with `ReadVol::ray`.
+pub struct Ray<'a, V: ReadVol, F: FnMut(&V::Vox) -> bool> {
+    vol: &'a V,
+    from: Vec3<f32>,
+    to: Vec3<f32>,
+    until: F,
+    ignore_error: bool,
+}
+
+impl<'a, V: ReadVol, F: FnMut(&V::Vox) -> bool> Ray<'a, V, F> {
+    pub fn new(vol: &'a V, from: Vec3<f32>, to: Vec3<f32>, until: F) -> Self {
+        Self {
+            vol,
+            from,
+            to,
+            until,
+            ignore_error: false,
+        }
+    }
+
+    /// Use a different predicate to decide which voxels stop the ray.
+    pub fn until<G: FnMut(&V::Vox) -> bool>(self, until: G) -> Ray<'a, V, G> {
+        Ray {
+            vol: self.vol,
+            from: self.from,
+            to: self.to,
+            until,
+            ignore_error: self.ignore_error,
+        }
+    }
+
+    /// Treat voxels that can't be accessed (for example, because they're outside the volume or
+    /// their chunk isn't loaded) as passable rather than stopping the ray with an error.
+    pub fn ignore_error(mut self) -> Self {
+        self.ignore_error = true;
+        self
+    }
+
+    /// Get an iterator over the voxel positions that this ray passes through, regardless of
+    /// their contents.
+    pub fn steps(&self) -> RaySteps {
+        RaySteps::new(self.from, self.to)
+    }
+
+    /// Cast the ray, returning the first voxel that satisfies the predicate (if any).
+    ///
+    /// If a voxel that can't be accessed is encountered before a hit, its error is returned
+    /// unless `ignore_error` has been used.
+    pub fn cast(mut self) -> Result<Option<RayStep>, V::Err> {
+        for step in self.steps() {
+            match self.vol.get(step.pos) {
+                Ok(vox) if (self.until)(vox) => return Ok(Some(step)),
+                Ok(_) => {},
+                Err(_) if self.ignore_error => {},
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The default ray predicate: any non-empty voxel stops the ray.
+pub fn is_solid<V: Vox>(vox: &V) -> bool {
+    !vox.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        figure::cell::Cell,
+        vol::WriteVol,
+        volumes::dyna::Dyna,
+    };
+
+    fn test_vol() -> Dyna<Cell, ()> {
+        let mut vol = Dyna::filled(Vec3::new(8, 8, 8), Cell::empty(), ());
+        vol.set(Vec3::new(5, 2, 2), Cell::new(Rgb::new(255, 0, 0))).unwrap();
+        vol.set(Vec3::new(2, 2, 6), Cell::new(Rgb::new(0, 255, 0))).unwrap();
+        vol
+    }
+
+    #[test]
+    fn hits_first_solid_voxel() {
+        let vol = test_vol();
+        let hit = vol
+            .ray(Vec3::new(0.5, 2.5, 2.5), Vec3::new(7.5, 2.5, 2.5))
+            .cast()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(hit.pos, Vec3::new(5, 2, 2));
+        assert_eq!(hit.normal, Vec3::new(-1, 0, 0));
+        assert!((hit.dist - 4.5).abs() < 0.0001);
+        assert!((hit.point.x - 5.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn misses_when_nothing_is_in_the_way() {
+        let vol = test_vol();
+        let hit = vol
+            .ray(Vec3::new(0.5, 0.5, 0.5), Vec3::new(7.5, 0.5, 0.5))
+            .cast()
+            .unwrap();
+        assert!(hit.is_none());
+
+        // The ray stops before reaching the voxel
+        let hit = vol
+            .ray(Vec3::new(0.5, 2.5, 2.5), Vec3::new(4.5, 2.5, 2.5))
+            .cast()
+            .unwrap();
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn diagonal_ray_visits_connected_voxels() {
+        let steps = RaySteps::new(Vec3::new(0.2, 0.7, 0.1), Vec3::new(6.3, 3.9, 5.5)).collect::<Vec<_>>();
+        assert_eq!(steps.first().unwrap().pos, Vec3::new(0, 0, 0));
+        assert_eq!(steps.last().unwrap().pos, Vec3::new(6, 3, 5));
+        for pair in steps.windows(2) {
+            // Each step moves exactly one voxel along exactly one axis
+            let diff = pair[1].pos - pair[0].pos;
+            assert_eq!(diff.map(|e| e.abs()).sum(), 1);
+            assert_eq!(pair[1].normal, -diff);
+            assert!(pair[1].dist >= pair[0].dist);
+        }
+    }
+
+    #[test]
+    fn custom_predicate_is_used() {
+        let vol = test_vol();
+        let hit = vol
+            .ray(Vec3::new(2.5, 2.5, 7.5), Vec3::new(2.5, 2.5, 0.5))
+            .until(|cell| cell.get_color().map(|col| col.r > 0).unwrap_or(false))
+            .cast()
+            .unwrap();
+        assert!(hit.is_none());
+
+        let hit = vol
+            .ray(Vec3::new(2.5, 2.5, 7.5), Vec3::new(2.5, 2.5, 0.5))
+            .cast()
+            .unwrap()
+            .unwrap();
+        assert_eq!(hit.pos, Vec3::new(2, 2, 6));
+        assert_eq!(hit.normal, Vec3::new(0, 0, 1));
+    }
+
+    #[test]
+    fn errors_stop_the_ray_unless_ignored() {
+        let vol = test_vol();
+        assert!(vol
+            .ray(Vec3::new(-2.5, 2.5, 2.5), Vec3::new(7.5, 2.5, 2.5))
+            .cast()
+            .is_err());
+
+        let hit = vol
+            .ray(Vec3::new(-2.5, 2.5, 2.5), Vec3::new(7.5, 2.5, 2.5))
+            .ignore_error()
+            .cast()
+            .unwrap()
+            .unwrap();
+        assert_eq!(hit.pos, Vec3::new(5, 2, 2));
+    }
+}
@@ -0,0 +1,288 @@
+//! # Spawning
+//! Which NPCs appear where is described by `SpawnRule`s, loaded from `assets/common/spawns.ron`.
+//! The world is split into regions (one per terrain chunk) and each rule keeps its own population
+//! topped up in the regions it applies to, up to a cap on the total population of a region.
+
+// Standard
+use std::collections::HashMap;
+
+// Library
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+use specs::{Component, VecStorage};
+use vek::*;
+
+// Crate
+use crate::{
+    assets,
+    comp::{
+        Body,
+        Temperament,
+        body::{bird, quadruped},
+        character::{Character, Gender, Race, Weapon},
+    },
+    state::DAY_SECONDS,
+    terrain::{BiomeKind, TerrainMap},
+};
+
+pub const SPAWN_RULES_ASSET: &str = "/common/spawns.ron";
+
+/// No more NPCs than this are spawned into a single region, whatever the rules say.
+pub const MAX_REGION_POPULATION: usize = 6;
+
+/// How a spawned creature behaves. This is the subset of `Temperament`s that make sense without
+/// knowing about other entities.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Behaviour {
+    Passive,
+    Timid,
+    Hostile,
+}
+
+impl From<Behaviour> for Temperament {
+    fn from(behaviour: Behaviour) -> Self {
+        match behaviour {
+            Behaviour::Passive => Temperament::Passive,
+            Behaviour::Timid => Temperament::Timid,
+            Behaviour::Hostile => Temperament::Hostile,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CreatureKind {
+    Humanoid { race: Race, weapon: Weapon },
+    Quadruped(quadruped::Species),
+    Bird(bird::Species),
+}
+
+/// The kind of creature a rule spawns.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Creature {
+    pub kind: CreatureKind,
+    pub behaviour: Behaviour,
+}
+
+impl Creature {
+    /// Make a body for an individual of this kind.
+    pub fn body<R: Rng>(&self, rng: &mut R) -> Body {
+        match self.kind {
+            CreatureKind::Humanoid { race, weapon } => {
+                let gender = if rng.gen() { Gender::Female } else { Gender::Male };
+                Body::Humanoid(Character::new(race, gender, weapon))
+            },
+            CreatureKind::Quadruped(species) => Body::Quadruped(quadruped::Body::new(species)),
+            CreatureKind::Bird(species) => Body::Bird(bird::Body::new(species)),
+        }
+    }
+}
+
+/// The part of the day during which a rule applies.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpawnTime {
+    Any,
+    /// From 6:00 to 18:00
+    Day,
+    Night,
+}
+
+impl SpawnTime {
+    /// Whether the given time of day (in in-game seconds) falls within this part of the day.
+    pub fn matches(self, time_of_day: f64) -> bool {
+        let hour = time_of_day.rem_euclid(DAY_SECONDS) / 3600.0;
+        let day = hour >= 6.0 && hour < 18.0;
+        match self {
+            SpawnTime::Any => true,
+            SpawnTime::Day => day,
+            SpawnTime::Night => !day,
+        }
+    }
+}
+
+impl Default for SpawnTime {
+    fn default() -> Self {
+        SpawnTime::Any
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpawnRule {
+    pub creature: Creature,
+    pub biomes: Vec<BiomeKind>,
+    /// The average number of these creatures in each region with a matching biome. Fractional
+    /// densities mean that only some regions get an extra creature.
+    pub density: f32,
+    #[serde(default)]
+    pub time: SpawnTime,
+    /// The smallest and largest number of creatures spawned together
+    pub group_size: (u32, u32),
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The spawn rules asset couldn't be loaded
+    MissingAsset,
+    Parse(ron::de::Error),
+}
+
+/// A group of NPCs that should be spawned, following the rule with the given index.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpawnGroup {
+    pub rule: usize,
+    pub count: u32,
+}
+
+/// Marks an NPC that was spawned by the rule with the given index, so that it can be counted
+/// towards its region's population and despawned when nobody is around.
+#[derive(Copy, Clone, Debug)]
+pub struct Spawned {
+    pub rule: usize,
+}
+
+impl Component for Spawned {
+    type Storage = VecStorage<Self>;
+}
+
+/// Get the key of the region containing the given position.
+pub fn region_key(pos: Vec3<f32>) -> Vec3<i32> {
+    pos.map2(TerrainMap::chunk_size(), |e, sz| (e.floor() as i32).div_euclid(sz as i32))
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SpawnRules {
+    rules: Vec<SpawnRule>,
+}
+
+impl SpawnRules {
+    /// Load the spawn rules from the game's assets.
+    pub fn load() -> Result<Self, Error> {
+        let bytes = assets::load(SPAWN_RULES_ASSET).map_err(|_| Error::MissingAsset)?;
+        let rules = ron::de::from_bytes(&bytes).map_err(Error::Parse)?;
+        Ok(Self::from_rules(rules))
+    }
+
+    pub fn from_rules(rules: Vec<SpawnRule>) -> Self {
+        Self { rules }
+    }
+
+    pub fn get(&self, rule: usize) -> Option<&SpawnRule> {
+        self.rules.get(rule)
+    }
+
+    /// Decide which groups to spawn in a region, given its biome, the time of day and how many
+    /// NPCs spawned by each rule are already there.
+    pub fn plan<R: Rng>(
+        &self,
+        rng: &mut R,
+        region: Vec3<i32>,
+        biome: BiomeKind,
+        time_of_day: f64,
+        population: &HashMap<usize, usize>,
+    ) -> Vec<SpawnGroup> {
+        let mut total = population.values().sum::<usize>();
+        let mut groups = Vec::new();
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            if total >= MAX_REGION_POPULATION {
+                break;
+            }
+            if !rule.biomes.contains(&biome) || !rule.time.matches(time_of_day) {
+                continue;
+            }
+
+            // Round fractional densities the same way every time for a given region, so that
+            // regions don't slowly fill up to the rounded-up density
+            let extra = region_roll(region, index) < rule.density.fract();
+            let target = rule.density.max(0.0).floor() as usize + extra as usize;
+            let existing = population.get(&index).cloned().unwrap_or(0);
+            if existing >= target {
+                continue;
+            }
+
+            let (min, max) = rule.group_size;
+            let count = (rng.gen_range(min.min(max), max.max(min) + 1) as usize)
+                .min(target - existing)
+                .min(MAX_REGION_POPULATION - total);
+            if count > 0 {
+                total += count;
+                groups.push(SpawnGroup { rule: index, count: count as u32 });
+            }
+        }
+
+        groups
+    }
+}
+
+/// A random number in `0.0..1.0` that's always the same for a given region and rule.
+fn region_roll(region: Vec3<i32>, rule: usize) -> f32 {
+    let mut seed = [0; 16];
+    for (i, e) in [region.x, region.y, region.z, rule as i32].iter().enumerate() {
+        seed[i * 4..i * 4 + 4].copy_from_slice(&e.to_le_bytes());
+    }
+    SmallRng::from_seed(seed).gen()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOON: f64 = 12.0 * 3600.0;
+    const MIDNIGHT: f64 = 0.0;
+
+    fn rules() -> SpawnRules {
+        ron::de::from_str(r#"[
+            (
+                creature: (kind: Humanoid(race: Human, weapon: Sword), behaviour: Passive),
+                biomes: [Grassland, Forest],
+                density: 3.0,
+                time: Day,
+                group_size: (1, 2),
+            ),
+            (
+                creature: (kind: Quadruped(Wolf), behaviour: Hostile),
+                biomes: [Grassland],
+                density: 5.0,
+                group_size: (5, 5),
+            ),
+        ]"#)
+            .map(SpawnRules::from_rules)
+            .unwrap()
+    }
+
+    #[test]
+    fn shipped_rules_load() {
+        assert!(SpawnRules::load().unwrap().get(0).is_some());
+    }
+
+    #[test]
+    fn plans_follow_biome_and_time() {
+        let rules = rules();
+        let mut rng = SmallRng::from_seed([3; 16]);
+        let empty = HashMap::new();
+
+        assert!(rules.plan(&mut rng, Vec3::zero(), BiomeKind::Desert, NOON, &empty).is_empty());
+
+        let night = rules.plan(&mut rng, Vec3::zero(), BiomeKind::Forest, MIDNIGHT, &empty);
+        assert!(night.is_empty());
+
+        let day = rules.plan(&mut rng, Vec3::zero(), BiomeKind::Forest, NOON, &empty);
+        assert_eq!(day.len(), 1);
+        assert_eq!(day[0].rule, 0);
+        assert!(day[0].count >= 1 && day[0].count <= 2);
+    }
+
+    #[test]
+    fn plans_respect_density_and_region_cap() {
+        let rules = rules();
+        let mut rng = SmallRng::from_seed([3; 16]);
+
+        // The region is already at the first rule's density
+        let mut population = HashMap::new();
+        population.insert(0, 3);
+        let groups = rules.plan(&mut rng, Vec3::zero(), BiomeKind::Grassland, NOON, &population);
+        assert_eq!(groups, vec![SpawnGroup { rule: 1, count: (MAX_REGION_POPULATION - 3) as u32 }]);
+
+        // Nothing more fits into a full region
+        population.insert(1, MAX_REGION_POPULATION - 3);
+        assert!(rules.plan(&mut rng, Vec3::zero(), BiomeKind::Grassland, NOON, &population).is_empty());
+    }
+}
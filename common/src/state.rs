@@ -2,14 +2,17 @@
 pub use sphynx::Uid;
 
 use std::{
-    time::Duration,
+    time::{Duration, Instant},
     collections::HashSet,
+    sync::{Arc, Mutex},
 };
-use shred::{Fetch, FetchMut};
+use shred::{Fetch, FetchMut, Resources};
 use specs::{
     Builder,
     Component,
+    Dispatcher,
     DispatcherBuilder,
+    System,
     EntityBuilder as EcsEntityBuilder,
     Entity as EcsEntity,
     storage::{
@@ -24,26 +27,61 @@ use crate::{
     comp,
     sys,
     terrain::{
+        Block,
         TerrainMap,
         TerrainChunk,
+        TerrainChunkDelta,
     },
+    vol::WriteVol,
+    volumes::vol_map::VolMapErr,
+    weather::WeatherMap,
     msg::EcsPacket,
+    path::Pathfinder,
 };
 
-/// How much faster should an in-game day be compared to a real day?
-// TODO: Don't hard-code this
-const DAY_CYCLE_FACTOR: f64 = 24.0 * 60.0;
+/// The length of an in-game day, in in-game seconds.
+pub const DAY_SECONDS: f64 = 24.0 * 60.0 * 60.0;
 
-/// A resource to store the time of day
+/// The default length of an in-game day, in real seconds.
+pub const DEFAULT_DAY_LENGTH: f64 = 20.0 * 60.0;
+
+/// A resource to store the time of day, in in-game seconds since midnight
 struct TimeOfDay(f64);
 
+/// A resource to store how many real seconds an in-game day lasts
+struct DayLength(f64);
+
 /// A resource to store the tick (i.e: physics) time
-struct Time(f64);
+#[derive(Default)]
+pub struct Time(pub f64);
 
 /// A resource used to store the time since the last tick
 #[derive(Default)]
 pub struct DeltaTime(pub f64);
 
+/// Configuration for fixed-timestep simulation.
+///
+/// When enabled, the time passed to `State::tick` is accumulated and the world is simulated in
+/// whole steps of exactly `step`, so that the simulation behaves identically regardless of how
+/// often `tick` is called.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FixedStep {
+    /// The duration of a single simulation step.
+    pub step: Duration,
+    /// The maximum number of steps simulated in a single call to `tick`. Any time beyond this is
+    /// discarded to avoid a 'spiral of death' when the simulation can't keep up.
+    pub max_steps: u32,
+}
+
+impl Default for FixedStep {
+    fn default() -> Self {
+        Self {
+            step: Duration::from_nanos(1_000_000_000 / 60),
+            max_steps: 8,
+        }
+    }
+}
+
 pub struct Changes {
     pub new_chunks: HashSet<Vec3<i32>>,
     pub changed_chunks: HashSet<Vec3<i32>>,
@@ -66,49 +104,125 @@ impl Changes {
     }
 }
 
+/// How long a system spent running during a tick. With a fixed timestep, a system may run
+/// several times (or not at all) in one tick, so this is accumulated over every step.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SysTiming {
+    pub total: Duration,
+    /// The number of steps the system ran in
+    pub runs: u32,
+}
+
+/// Wraps a system, accumulating how long it spends running during the current tick.
+struct Timed<S> {
+    sys: S,
+    timing: Arc<Mutex<SysTiming>>,
+}
+
+impl<'a, S: System<'a>> System<'a> for Timed<S> {
+    type SystemData = S::SystemData;
+
+    fn run(&mut self, data: Self::SystemData) {
+        let start = Instant::now();
+        self.sys.run(data);
+
+        let mut timing = self.timing.lock().unwrap();
+        timing.total += start.elapsed();
+        timing.runs += 1;
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        self.sys.setup(res);
+    }
+}
+
+/// The systems run by a `State`. Systems can only be added before the first tick, at which point
+/// the dispatcher is built and kept for the lifetime of the state.
+enum Systems {
+    Building(DispatcherBuilder<'static, 'static>),
+    Built(Dispatcher<'static, 'static>),
+}
+
 /// A type used to represent game state stored on both the client and the server. This includes
 /// things like entity components, terrain data, and global state like weather, time of day, etc.
 pub struct State {
     ecs: sphynx::World<EcsPacket>,
     changes: Changes,
+    fixed_step: Option<FixedStep>,
+    accumulator: Duration,
+    systems: Option<Systems>,
+    sys_timings: Vec<(&'static str, Arc<Mutex<SysTiming>>)>,
 }
 
 impl State {
     /// Create a new `State`.
     pub fn new() -> Self {
-        Self {
-            ecs: sphynx::World::new(specs::World::new(), Self::setup_sphynx_world),
-            changes: Changes::default(),
-        }
+        let mut this = Self::from_ecs(sphynx::World::new(specs::World::new(), Self::setup_sphynx_world));
+        sys::add_local_systems(&mut this, &[]);
+        this
+    }
+
+    /// Create a new `State` in which `sys` runs before the control system, so that the controls it
+    /// produces are applied in the same tick. The server uses this to drive agents.
+    pub fn with_input_system<S>(sys: S, name: &'static str) -> Self
+        where S: for<'a> System<'a> + Send + 'static
+    {
+        let mut this = Self::from_ecs(sphynx::World::new(specs::World::new(), Self::setup_sphynx_world));
+        this.add_system(sys, name, &[]);
+        sys::add_local_systems(&mut this, &[name]);
+        this
     }
 
     /// Create a new `State` from an ECS state package
     pub fn from_state_package(state_package: sphynx::StatePackage<EcsPacket>) -> Self {
+        let mut this = Self::from_ecs(sphynx::World::from_state_package(specs::World::new(), Self::setup_sphynx_world, state_package));
+        sys::add_local_systems(&mut this, &[]);
+        this
+    }
+
+    fn from_ecs(ecs: sphynx::World<EcsPacket>) -> Self {
         Self {
-            ecs: sphynx::World::from_state_package(specs::World::new(), Self::setup_sphynx_world, state_package),
+            ecs,
             changes: Changes::default(),
+            fixed_step: None,
+            accumulator: Duration::default(),
+            systems: Some(Systems::Building(DispatcherBuilder::new())),
+            sys_timings: Vec::new(),
         }
     }
 
     // Create a new Sphynx ECS world
     fn setup_sphynx_world(ecs: &mut sphynx::World<EcsPacket>) {
         // Register synced components
-        ecs.register_synced::<comp::Character>();
+        ecs.register_synced::<comp::Body>();
         ecs.register_synced::<comp::Player>();
+        ecs.register_synced::<comp::Stats>();
+        ecs.register_synced::<comp::ItemDrop>();
+        ecs.register_synced::<comp::Mount>();
+        ecs.register_synced::<comp::Rider>();
 
         // Register unsynched (or synced by other means) components
         ecs.internal_mut().register::<comp::phys::Pos>();
         ecs.internal_mut().register::<comp::phys::Vel>();
         ecs.internal_mut().register::<comp::phys::Dir>();
+        ecs.internal_mut().register::<comp::phys::OnGround>();
         ecs.internal_mut().register::<comp::AnimationHistory>();
         ecs.internal_mut().register::<comp::Agent>();
         ecs.internal_mut().register::<comp::Control>();
+        ecs.internal_mut().register::<comp::MovementStats>();
+        ecs.internal_mut().register::<comp::Dead>();
+        ecs.internal_mut().register::<comp::Attacking>();
+        ecs.internal_mut().register::<comp::Inventory>();
 
         // Register resources used by the ECS
         ecs.internal_mut().add_resource(TimeOfDay(0.0));
+        ecs.internal_mut().add_resource(DayLength(DEFAULT_DAY_LENGTH));
         ecs.internal_mut().add_resource(Time(0.0));
         ecs.internal_mut().add_resource(DeltaTime(0.0));
         ecs.internal_mut().add_resource(TerrainMap::new());
+        ecs.internal_mut().add_resource(WeatherMap::new());
+        ecs.internal_mut().add_resource(Pathfinder::new());
+        ecs.internal_mut().add_resource(comp::DamageQueue::default());
     }
 
     /// Register a component with the state's ECS
@@ -119,6 +233,68 @@ impl State {
         self
     }
 
+    /// Add a system to be run every tick, after the systems named in `deps`. See `add_system`.
+    pub fn with_system<S>(mut self, sys: S, name: &'static str, deps: &[&str]) -> Self
+        where S: for<'a> System<'a> + Send + 'static
+    {
+        self.add_system(sys, name, deps);
+        self
+    }
+
+    /// Add a system to be run every tick, after the systems named in `deps`. The common systems
+    /// are always present, so their names (see `sys`) may be used as dependencies.
+    ///
+    /// Panics if the state has already been ticked, since the dispatcher can't be changed after
+    /// it has been built.
+    pub fn add_system<S>(&mut self, sys: S, name: &'static str, deps: &[&str])
+        where S: for<'a> System<'a> + Send + 'static
+    {
+        let timing = Arc::new(Mutex::new(SysTiming::default()));
+        match &mut self.systems {
+            Some(Systems::Building(builder)) => builder.add(Timed { sys, timing: timing.clone() }, name, deps),
+            _ => panic!("Systems can't be added to a state that has already been ticked"),
+        }
+        self.sys_timings.push((name, timing));
+    }
+
+    /// Get how long each system spent running during the last tick, in the order they were added.
+    pub fn sys_timings(&self) -> Vec<(&'static str, SysTiming)> {
+        self.sys_timings
+            .iter()
+            .map(|(name, timing)| (*name, *timing.lock().unwrap()))
+            .collect()
+    }
+
+    /// Simulate the state in fixed timesteps (see `FixedStep`).
+    pub fn with_fixed_step(mut self, fixed_step: FixedStep) -> Self {
+        self.set_fixed_step(Some(fixed_step));
+        self
+    }
+
+    /// Change how the state is simulated. `None` means that each call to `tick` simulates exactly
+    /// the duration passed to it.
+    pub fn set_fixed_step(&mut self, fixed_step: Option<FixedStep>) {
+        self.fixed_step = fixed_step;
+        self.accumulator = Duration::default();
+    }
+
+    /// Get the fixed timestep configuration of the state, if any.
+    pub fn fixed_step(&self) -> Option<FixedStep> {
+        self.fixed_step
+    }
+
+    /// Get how far (between 0 and 1) the accumulated time lies between the last simulated step
+    /// and the next one. Renderers should use this to interpolate between the previous and
+    /// current state of entities.
+    ///
+    /// Without a fixed timestep, this is always 1 since the state is always fully up to date.
+    pub fn alpha(&self) -> f32 {
+        match self.fixed_step {
+            Some(fixed_step) => (self.accumulator.as_secs_f64() / fixed_step.step.as_secs_f64()) as f32,
+            None => 1.0,
+        }
+    }
+
     /// Write a component attributed to a particular entity
     pub fn write_component<C: Component>(&mut self, entity: EcsEntity, comp: C) {
         let _ = self.ecs.internal_mut().write_storage().insert(entity, comp);
@@ -157,6 +333,26 @@ impl State {
         self.ecs.internal().read_resource::<TimeOfDay>().0
     }
 
+    /// Set the current in-game time of day, in in-game seconds since midnight.
+    pub fn set_time_of_day(&mut self, time_of_day: f64) {
+        self.ecs.internal_mut().write_resource::<TimeOfDay>().0 = time_of_day.rem_euclid(DAY_SECONDS);
+    }
+
+    /// Get how many real seconds an in-game day lasts.
+    pub fn get_day_length(&self) -> f64 {
+        self.ecs.internal().read_resource::<DayLength>().0
+    }
+
+    /// Set how many real seconds an in-game day lasts. Returns false (leaving the day length
+    /// unchanged) if the length isn't a positive number of seconds.
+    pub fn set_day_length(&mut self, day_length: f64) -> bool {
+        if !(day_length > 0.0 && day_length.is_finite()) {
+            return false;
+        }
+        self.ecs.internal_mut().write_resource::<DayLength>().0 = day_length;
+        true
+    }
+
     /// Get the current in-game time.
     ///
     /// Note that this does not correspond to the time of day.
@@ -171,6 +367,20 @@ impl State {
             .read_resource::<TerrainMap>()
     }
 
+    /// Get a reference to this state's weather.
+    pub fn weather(&self) -> Fetch<WeatherMap> {
+        self.ecs
+            .internal()
+            .read_resource::<WeatherMap>()
+    }
+
+    /// Get a mutable reference to this state's weather.
+    pub fn weather_mut(&mut self) -> FetchMut<WeatherMap> {
+        self.ecs
+            .internal_mut()
+            .write_resource::<WeatherMap>()
+    }
+
     /// Insert the provided chunk into this state's terrain.
     pub fn insert_chunk(&mut self, key: Vec3<i32>, chunk: TerrainChunk) {
         if self.ecs
@@ -183,22 +393,109 @@ impl State {
         } else {
             self.changes.new_chunks.insert(key);
         }
+        self.invalidate_paths(key);
+    }
+
+    /// Set the block at the provided position in this state's terrain, marking the chunk that
+    /// contains it as changed.
+    pub fn set_block(&mut self, pos: Vec3<i32>, block: Block) -> Result<(), VolMapErr> {
+        let mut terrain = self.ecs
+            .internal_mut()
+            .write_resource::<TerrainMap>();
+
+        terrain.set(pos, block)?;
+        let key = terrain.pos_key(pos);
+        drop(terrain);
+
+        self.changes.changed_chunks.insert(key);
+        self.invalidate_paths(key);
+        Ok(())
+    }
+
+    /// Apply a delta to the chunk with the provided key, marking it as changed.
+    pub fn apply_chunk_delta(&mut self, key: Vec3<i32>, delta: &TerrainChunkDelta) -> Result<(), VolMapErr> {
+        self.ecs
+            .internal_mut()
+            .write_resource::<TerrainMap>()
+            .get_key_mut(key)
+            .ok_or(VolMapErr::NoSuchChunk)?
+            .apply_delta(delta)
+            .map_err(VolMapErr::ChunkErr)?;
+
+        self.changes.changed_chunks.insert(key);
+        self.invalidate_paths(key);
+        Ok(())
+    }
+
+    /// Throw away any paths that depend on the chunk with the provided key.
+    fn invalidate_paths(&mut self, key: Vec3<i32>) {
+        self.ecs
+            .internal_mut()
+            .write_resource::<Pathfinder>()
+            .invalidate_chunk(key);
     }
 
     /// Execute a single tick, simulating the game state by the given duration.
-    pub fn tick(&mut self, dt: Duration) {
+    ///
+    /// With a fixed timestep, this simulates as many whole steps as fit into the accumulated time
+    /// (up to `max_steps`), carrying over the remainder to the next tick. Returns the number of
+    /// steps simulated.
+    pub fn tick(&mut self, dt: Duration) -> u32 {
+        for (_, timing) in &self.sys_timings {
+            *timing.lock().unwrap() = SysTiming::default();
+        }
+
+        let fixed_step = match self.fixed_step {
+            Some(fixed_step) => fixed_step,
+            None => {
+                self.step(dt);
+                return 1;
+            },
+        };
+
+        self.accumulator += dt;
+
+        let mut steps = 0;
+        while self.accumulator >= fixed_step.step && steps < fixed_step.max_steps {
+            self.step(fixed_step.step);
+            self.accumulator -= fixed_step.step;
+            steps += 1;
+        }
+
+        // We couldn't catch up, so drop the time we're behind by rather than falling further behind
+        if self.accumulator >= fixed_step.step {
+            self.accumulator = Duration::from_nanos(
+                (self.accumulator.as_nanos() % fixed_step.step.as_nanos()) as u64,
+            );
+        }
+
+        steps
+    }
+
+    /// Simulate the game state by exactly the given duration.
+    fn step(&mut self, dt: Duration) {
         // Change the time accordingly
-        self.ecs.internal_mut().write_resource::<TimeOfDay>().0 += dt.as_secs_f64() * DAY_CYCLE_FACTOR;
+        let day_cycle_factor = DAY_SECONDS / self.get_day_length();
+        let time_of_day = self.get_time_of_day() + dt.as_secs_f64() * day_cycle_factor;
+        self.set_time_of_day(time_of_day);
         self.ecs.internal_mut().write_resource::<Time>().0 += dt.as_secs_f64();
 
         // Run systems to update the world
         self.ecs.internal_mut().write_resource::<DeltaTime>().0 = dt.as_secs_f64();
 
-        // Create and run dispatcher for ecs systems
-        let mut dispatch_builder = DispatcherBuilder::new();
-        sys::add_local_systems(&mut dispatch_builder);
+        // Build the dispatcher the first time it's needed, after which no more systems may be added
+        let mut dispatcher = match self.systems.take() {
+            Some(Systems::Building(builder)) => {
+                let mut dispatcher = builder.build();
+                dispatcher.setup(&mut self.ecs.internal_mut().res);
+                dispatcher
+            },
+            Some(Systems::Built(dispatcher)) => dispatcher,
+            None => unreachable!(),
+        };
         // This dispatches all the systems in parallel
-        dispatch_builder.build().dispatch(&self.ecs.internal_mut().res);
+        dispatcher.dispatch(&self.ecs.internal_mut().res);
+        self.systems = Some(Systems::Built(dispatcher));
 
         self.ecs.internal_mut().maintain();
     }
@@ -209,3 +506,110 @@ impl State {
         self.changes.cleanup();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::Join;
+
+    fn fixed_state() -> State {
+        State::new().with_fixed_step(FixedStep {
+            step: Duration::from_millis(10),
+            max_steps: 4,
+        })
+    }
+
+    struct CountTicks;
+
+    impl<'a> System<'a> for CountTicks {
+        type SystemData = specs::Write<'a, u32>;
+
+        fn run(&mut self, mut ticks: Self::SystemData) {
+            *ticks += 1;
+        }
+    }
+
+    #[test]
+    fn added_systems_run_every_tick() {
+        let mut state = State::new()
+            .with_system(CountTicks, "count_ticks", &[sys::MOVEMENT_SYS]);
+
+        for _ in 0..3 {
+            state.tick(Duration::from_millis(10));
+        }
+
+        assert_eq!(*state.ecs().internal().read_resource::<u32>(), 3);
+        assert!(state.sys_timings().iter().any(|(name, _)| *name == "count_ticks"));
+        assert!(state.sys_timings().iter().any(|(name, _)| *name == sys::MOVEMENT_SYS));
+    }
+
+    struct Attack;
+
+    impl<'a> System<'a> for Attack {
+        type SystemData = specs::WriteStorage<'a, comp::Control>;
+
+        fn run(&mut self, mut controls: Self::SystemData) {
+            for control in (&mut controls).join() {
+                control.attack = true;
+            }
+        }
+    }
+
+    #[test]
+    fn input_systems_run_before_control() {
+        let mut state = State::with_input_system(Attack, "attack");
+        let entity = state
+            .ecs_mut()
+            .internal_mut()
+            .create_entity()
+            .with(comp::phys::Vel(Vec3::zero()))
+            .with(comp::phys::Dir(Vec3::unit_x()))
+            .with(comp::Control::default())
+            .build();
+
+        state.tick(Duration::from_millis(10));
+
+        assert!(state.read_storage::<comp::Attacking>().get(entity).is_some());
+    }
+
+    #[test]
+    fn sys_timings_cover_every_step_of_a_tick() {
+        let mut state = fixed_state();
+        let runs = |state: &State| state
+            .sys_timings()
+            .iter()
+            .find(|(name, _)| *name == sys::MOVEMENT_SYS)
+            .map(|(_, timing)| timing.runs);
+
+        state.tick(Duration::from_millis(25));
+        assert_eq!(runs(&state), Some(2));
+
+        // Timings from earlier ticks aren't reported again
+        state.tick(Duration::from_millis(1));
+        assert_eq!(runs(&state), Some(0));
+    }
+
+    #[test]
+    fn fixed_step_carries_over_remainder() {
+        let mut state = fixed_state();
+
+        assert_eq!(state.tick(Duration::from_millis(25)), 2);
+        assert!((state.get_time() - 0.02).abs() < 0.000001);
+        assert!((state.alpha() - 0.5).abs() < 0.0001);
+
+        assert_eq!(state.tick(Duration::from_millis(5)), 1);
+        assert!((state.get_time() - 0.03).abs() < 0.000001);
+        assert!(state.alpha().abs() < 0.0001);
+    }
+
+    #[test]
+    fn fixed_step_catch_up_is_limited() {
+        let mut state = fixed_state();
+
+        assert_eq!(state.tick(Duration::from_millis(1003)), 4);
+        assert!((state.get_time() - 0.04).abs() < 0.000001);
+        // The excess time is dropped rather than simulated later
+        assert!((state.alpha() - 0.3).abs() < 0.0001);
+        assert_eq!(state.tick(Duration::from_millis(0)), 0);
+    }
+}
@@ -1,34 +1,353 @@
 // Library
-use specs::{Join, Read, ReadStorage, System, WriteStorage};
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+use specs::{Entities, Entity as EcsEntity, Join, Read, ReadExpect, ReadStorage, System, Write, WriteStorage};
 use vek::*;
 
 // Crate
-use crate::comp::{Agent, Control, phys::Pos};
+use crate::{
+    comp::{Agent, AgentState, Control, Gait, Mount, Player, Stats, Temperament, phys::Pos},
+    path::{self, Pathfinder},
+    state::Time,
+    terrain::TerrainMap,
+    vol::{ReadVol, Vox},
+};
 
-// Basic ECS AI agent system
-pub struct Sys;
+// Agents stop this far from the entity they're following
+const FOLLOW_DISTANCE: f32 = 3.0;
+// Agents attack targets within this distance (a little less than the reach of an attack)
+const ATTACK_DISTANCE: f32 = 2.0;
+// Agents are considered to have arrived at their destination within this distance
+const ARRIVE_DISTANCE: f32 = 1.0;
+// Agents flee when their health falls below this fraction
+const FLEE_HEALTH: f32 = 0.25;
+const MIN_IDLE_TIME: f64 = 2.0;
+const MAX_IDLE_TIME: f64 = 6.0;
+// How far ahead agents look for obstacles
+const OBSTACLE_LOOKAHEAD: f32 = 0.8;
+
+/// Another entity that an agent can see.
+#[derive(Copy, Clone, Debug)]
+pub struct Seen {
+    pub entity: EcsEntity,
+    pub pos: Vec3<f32>,
+    pub is_player: bool,
+    pub alive: bool,
+}
+
+/// Everything an agent knows about its surroundings when deciding what to do.
+#[derive(Clone, Debug)]
+pub struct Perception {
+    pub pos: Vec3<f32>,
+    pub health_fraction: f32,
+    /// The other entities within the agent's sight radius, nearest first
+    pub nearby: Vec<Seen>,
+    /// The next point along the path to where the agent is going, if a path has been found
+    pub route: Option<Vec3<f32>>,
+}
+
+impl Perception {
+    fn find(&self, entity: EcsEntity) -> Option<&Seen> {
+        self.nearby.iter().find(|seen| seen.entity == entity)
+    }
+
+    fn nearest_player(&self) -> Option<&Seen> {
+        self.nearby.iter().find(|seen| seen.is_player && seen.alive)
+    }
+}
+
+/// Decide what an agent should be doing, given what it can perceive.
+pub fn think<R: Rng>(agent: &Agent, perception: &Perception, time: f64, rng: &mut R) -> AgentState {
+    let visible = |entity| perception.find(entity).filter(|seen| seen.alive);
+    let near_home = |pos: Vec3<f32>| Vec2::<f32>::from(pos).distance(agent.home) < agent.home_radius * 2.0;
+
+    // Running away takes priority over everything else
+    if let Some(threat) = perception.nearest_player() {
+        let flee = match agent.temperament {
+            Temperament::Timid => true,
+            Temperament::Passive | Temperament::Hostile => perception.health_fraction < FLEE_HEALTH,
+            Temperament::Companion(_) => false,
+        };
+        if flee {
+            return AgentState::Flee { from: threat.entity };
+        }
+    }
+
+    match agent.state {
+        // Keep fleeing until the threat is out of sight
+        AgentState::Flee { from } if visible(from).is_some() => return agent.state,
+        // Keep attacking until the target is dead, out of sight or has been chased too far
+        AgentState::Attack { target } if visible(target).map(|t| near_home(t.pos)).unwrap_or(false) =>
+            return agent.state,
+        _ => {},
+    }
+
+    match agent.temperament {
+        Temperament::Hostile => if let Some(target) = perception.nearest_player() {
+            if near_home(target.pos) {
+                return AgentState::Attack { target: target.entity };
+            }
+        },
+        Temperament::Companion(owner) => if visible(owner).is_some() {
+            return AgentState::Follow { target: owner };
+        },
+        Temperament::Passive | Temperament::Timid => {},
+    }
+
+    // With nothing else to do, alternate between standing around and wandering near home
+    match agent.state {
+        AgentState::Idle { until } if time < until => agent.state,
+        AgentState::Wander { dest } if Vec2::<f32>::from(perception.pos).distance(dest) > ARRIVE_DISTANCE =>
+            agent.state,
+        AgentState::Idle { .. } => {
+            let angle = rng.gen_range(0.0, std::f32::consts::PI * 2.0);
+            let dist = rng.gen_range(0.0, agent.home_radius);
+            AgentState::Wander { dest: agent.home + Vec2::new(angle.cos(), angle.sin()) * dist }
+        },
+        _ => AgentState::Idle { until: time + rng.gen_range(MIN_IDLE_TIME, MAX_IDLE_TIME) },
+    }
+}
+
+/// Where an agent needs to find a path to in order to do what it's doing, if anywhere. Nearby
+/// targets are moved towards directly.
+pub fn goal(agent: &Agent, perception: &Perception) -> Option<Vec3<f32>> {
+    let distant = |target: &Seen, dist| {
+        Vec2::<f32>::from(target.pos).distance(Vec2::from(perception.pos)) > dist
+    };
+
+    match agent.state {
+        // Destinations are only known across the ground, so look for it from a little above
+        AgentState::Wander { dest } => Some(Vec3::new(dest.x, dest.y, perception.pos.z + 4.0)),
+        AgentState::Follow { target } => perception.find(target)
+            .filter(|target| distant(target, FOLLOW_DISTANCE))
+            .map(|target| target.pos),
+        AgentState::Attack { target } => perception.find(target)
+            .filter(|target| distant(target, ATTACK_DISTANCE))
+            .map(|target| target.pos),
+        AgentState::Idle { .. } | AgentState::Flee { .. } => None,
+    }
+}
+
+/// Work out how an agent should move to carry out what it's doing. `is_solid` tells whether the
+/// block at a position is solid, so that agents can jump over small obstacles.
+pub fn act<F: Fn(Vec3<i32>) -> bool>(agent: &Agent, perception: &Perception, is_solid: F) -> Control {
+    let pos = perception.pos;
+    let towards = |target: Vec2<f32>| (target - Vec2::<f32>::from(pos)).try_normalized().unwrap_or(Vec2::zero());
+    // Follow the path to the goal when there is one
+    let steer = |goal: Vec2<f32>| towards(perception.route.map(Vec2::from).unwrap_or(goal));
+    let target_pos = |entity| perception.find(entity).map(|seen| Vec2::<f32>::from(seen.pos));
+
+    let mut control = Control::default();
+    match agent.state {
+        AgentState::Idle { .. } => {},
+        AgentState::Wander { dest } => {
+            control.move_dir = steer(dest);
+            control.gait = Gait::Walk;
+        },
+        AgentState::Follow { target } => if let Some(target_pos) = target_pos(target) {
+            if target_pos.distance(Vec2::<f32>::from(pos)) > FOLLOW_DISTANCE {
+                control.move_dir = steer(target_pos);
+            }
+        },
+        AgentState::Flee { from } => if let Some(from_pos) = target_pos(from) {
+            control.move_dir = -towards(from_pos);
+            control.gait = Gait::Sprint;
+        },
+        AgentState::Attack { target } => if let Some(target_pos) = target_pos(target) {
+            if target_pos.distance(Vec2::<f32>::from(pos)) > ATTACK_DISTANCE {
+                control.move_dir = steer(target_pos);
+            } else {
+                // Just enough movement to turn towards the target
+                control.move_dir = towards(target_pos) * 0.05;
+                control.attack = true;
+            }
+        },
+    }
+
+    // Jump up steps, as long as there's room to do so
+    if control.move_dir.magnitude_squared() > 0.01 {
+        let ahead = pos + Vec3::from(control.move_dir.normalized() * OBSTACLE_LOOKAHEAD);
+        let block_at = |z: f32| is_solid((ahead + Vec3::unit_z() * z).map(|e| e.floor() as i32));
+        control.jump = block_at(0.5) && !block_at(1.5) && !block_at(2.5);
+    }
+
+    control
+}
+
+/// Drives NPCs. This only runs on the server, since agents aren't synced.
+pub struct Sys {
+    rng: SmallRng,
+}
+
+impl Sys {
+    pub fn new() -> Self {
+        Self::with_rng(SmallRng::from_rng(rand::thread_rng()).expect("Failed to seed agent RNG"))
+    }
+
+    /// Create the system with a seeded RNG, making agents behave deterministically.
+    pub fn with_seed(seed: [u8; 16]) -> Self {
+        Self::with_rng(SmallRng::from_seed(seed))
+    }
+
+    fn with_rng(rng: SmallRng) -> Self {
+        Self { rng }
+    }
+}
+
+impl Default for Sys {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl<'a> System<'a> for Sys {
     type SystemData = (
-        WriteStorage<'a, Agent>,
+        Entities<'a>,
+        Read<'a, Time>,
+        ReadExpect<'a, TerrainMap>,
         ReadStorage<'a, Pos>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Stats>,
+        ReadStorage<'a, Mount>,
+        WriteStorage<'a, Agent>,
         WriteStorage<'a, Control>,
+        Write<'a, Pathfinder>,
     );
 
-    fn run(&mut self, (mut agents, pos, mut controls): Self::SystemData) {
-        for (mut agent, pos, mut control) in (&mut agents, &pos, &mut controls).join() {
-            match agent {
-                Agent::Wanderer(bearing) => {
-                    *bearing += Vec2::new(
-                        rand::random::<f32>().fract() - 0.5,
-                        rand::random::<f32>().fract() - 0.5,
-                    ) - *bearing * 0.05 - pos.0 * 0.001;
-
-                    if bearing.magnitude_squared() != 0.0 {
-                        control.move_dir = bearing.normalized();
-                    }
-                },
+    fn run(
+        &mut self,
+        (entities, time, terrain, positions, players, stats, mounts, mut agents, mut controls, mut pathfinder): Self::SystemData,
+    ) {
+        let is_solid = |pos: Vec3<i32>| terrain.get(pos).map(|block| !block.is_empty()).unwrap_or(false);
+
+        // Creatures that are being ridden go wherever their rider steers them
+        for (entity, agent, pos, control, _) in (&entities, &mut agents, &positions, &mut controls, !&mounts).join() {
+            let mut nearby = (&entities, &positions, players.maybe(), stats.maybe())
+                .join()
+                .filter(|(other, other_pos, _, _)| {
+                    *other != entity && other_pos.0.distance(pos.0) < agent.sight_radius
+                })
+                .map(|(other, other_pos, player, stats)| Seen {
+                    entity: other,
+                    pos: other_pos.0,
+                    is_player: player.is_some(),
+                    alive: stats.map(|stats| !stats.is_dead()).unwrap_or(true),
+                })
+                .collect::<Vec<_>>();
+            nearby.sort_by(|a, b| {
+                a.pos.distance_squared(pos.0)
+                    .partial_cmp(&b.pos.distance_squared(pos.0))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+
+            let mut perception = Perception {
+                pos: pos.0,
+                health_fraction: stats.get(entity).map(|stats| stats.health_fraction()).unwrap_or(1.0),
+                nearby,
+                route: None,
+            };
+
+            agent.state = think(agent, &perception, time.0, &mut self.rng);
+            perception.route = goal(agent, &perception).and_then(|goal| pathfinder.route(entity, pos.0, goal));
+            *control = act(agent, &perception, &is_solid);
+        }
+
+        pathfinder.update(&is_solid, path::DEFAULT_BUDGET);
+        pathfinder.retain(|entity| agents.get(entity).is_some());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{Builder, World};
+
+    const SEED: [u8; 16] = [7; 16];
+
+    fn perceive(pos: Vec3<f32>, health_fraction: f32, nearby: Vec<Seen>) -> Perception {
+        Perception { pos, health_fraction, nearby, route: None }
+    }
+
+    #[test]
+    fn wandering_stays_near_home_and_is_deterministic() {
+        let home = Vec2::new(100.0, -50.0);
+        let run = || {
+            let mut rng = SmallRng::from_seed(SEED);
+            let mut agent = Agent::new(Temperament::Passive, home).with_home_radius(10.0);
+            let mut dests = Vec::new();
+            for i in 0..200 {
+                // Teleport the agent to wherever it's going, so that it keeps picking new spots
+                let pos = match agent.state {
+                    AgentState::Wander { dest } => Vec3::from(dest),
+                    _ => Vec3::from(home),
+                };
+                agent.state = think(&agent, &perceive(pos, 1.0, Vec::new()), i as f64 * 10.0, &mut rng);
+                if let AgentState::Wander { dest } = agent.state {
+                    dests.push(dest);
+                }
             }
+            dests
+        };
+
+        let dests = run();
+        assert!(dests.len() > 10);
+        assert!(dests.iter().all(|dest| dest.distance(home) <= 10.0));
+        assert_eq!(dests, run());
+    }
+
+    #[test]
+    fn hostile_agents_attack_then_flee_when_hurt() {
+        let mut world = World::new();
+        let player = world.create_entity().build();
+        let mut rng = SmallRng::from_seed(SEED);
+        let mut agent = Agent::new(Temperament::Hostile, Vec2::zero());
+
+        let seen = |pos| vec![Seen { entity: player, pos, is_player: true, alive: true }];
+
+        // Chase the player when it's out of reach
+        agent.state = think(&agent, &perceive(Vec3::zero(), 1.0, seen(Vec3::new(5.0, 0.0, 0.0))), 0.0, &mut rng);
+        assert_eq!(agent.state, AgentState::Attack { target: player });
+        let control = act(&agent, &perceive(Vec3::zero(), 1.0, seen(Vec3::new(5.0, 0.0, 0.0))), |_| false);
+        assert!(!control.attack);
+        assert!(control.move_dir.x > 0.9);
+
+        // Attack once close enough
+        let control = act(&agent, &perceive(Vec3::zero(), 1.0, seen(Vec3::new(1.0, 0.0, 0.0))), |_| false);
+        assert!(control.attack);
+
+        // Run away when badly hurt
+        agent.state = think(&agent, &perceive(Vec3::zero(), 0.1, seen(Vec3::new(1.0, 0.0, 0.0))), 1.0, &mut rng);
+        assert_eq!(agent.state, AgentState::Flee { from: player });
+        let control = act(&agent, &perceive(Vec3::zero(), 0.1, seen(Vec3::new(1.0, 0.0, 0.0))), |_| false);
+        assert!(control.move_dir.x < -0.9);
+
+        // Calm down once the player is out of sight
+        agent.state = think(&agent, &perceive(Vec3::zero(), 0.1, Vec::new()), 2.0, &mut rng);
+        match agent.state {
+            AgentState::Idle { .. } | AgentState::Wander { .. } => {},
+            state => panic!("Unexpected state {:?}", state),
         }
     }
+
+    #[test]
+    fn agents_jump_up_steps_but_not_walls() {
+        let mut world = World::new();
+        let owner = world.create_entity().build();
+        let agent = Agent {
+            state: AgentState::Follow { target: owner },
+            ..Agent::new(Temperament::Companion(owner), Vec2::zero())
+        };
+        let perception = perceive(
+            Vec3::new(0.5, 0.5, 0.0),
+            1.0,
+            vec![Seen { entity: owner, pos: Vec3::new(10.5, 0.5, 0.0), is_player: true, alive: true }],
+        );
+
+        let step = |pos: Vec3<i32>| pos.x == 1 && pos.z == 0;
+        assert!(act(&agent, &perception, step).jump);
+
+        let wall = |pos: Vec3<i32>| pos.x == 1 && pos.z < 3;
+        assert!(!act(&agent, &perception, wall).jump);
+
+        assert!(!act(&agent, &perception, |_| false).jump);
+    }
 }
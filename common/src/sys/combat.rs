@@ -0,0 +1,71 @@
+// Library
+use specs::{Entities, Join, ReadStorage, System, Write, WriteStorage};
+use vek::*;
+
+// Crate
+use crate::comp::{
+    combat::Attacking,
+    phys::{Pos, Vel, Dir, ForceUpdate},
+    Damage,
+    DamageQueue,
+    Stats,
+};
+
+// TODO: Make these depend on the attacker's weapon
+const ATTACK_RANGE: f32 = 2.5;
+// The cosine of half the angle of the arc in front of the attacker that attacks hit
+const ATTACK_ARC_COS: f32 = 0.5;
+const ATTACK_DAMAGE: u32 = 10;
+const KNOCKBACK_SPEED: f32 = 8.0;
+const KNOCKBACK_LIFT: f32 = 4.0;
+
+/// Resolves the hits of new attacks against nearby entities. This only runs on the server.
+pub struct Sys;
+
+impl<'a> System<'a> for Sys {
+    type SystemData = (
+        Entities<'a>,
+        Write<'a, DamageQueue>,
+        ReadStorage<'a, Pos>,
+        ReadStorage<'a, Dir>,
+        ReadStorage<'a, Stats>,
+        WriteStorage<'a, Vel>,
+        WriteStorage<'a, Attacking>,
+        WriteStorage<'a, ForceUpdate>,
+    );
+
+    fn run(&mut self, (entities, mut damages, positions, dirs, stats, mut velocities, mut attackings, mut force_updates): Self::SystemData) {
+        for (attacker, attacker_pos, attacker_dir, attacking) in (&entities, &positions, &dirs, &mut attackings).join() {
+            if attacking.applied {
+                continue;
+            }
+            attacking.applied = true;
+
+            let facing = Vec2::from(attacker_dir.0).try_normalized().unwrap_or(Vec2::unit_y());
+
+            for (target, target_pos, target_stats, target_vel) in (&entities, &positions, &stats, &mut velocities).join() {
+                if target == attacker || target_stats.is_dead() {
+                    continue;
+                }
+
+                let offset = target_pos.0 - attacker_pos.0;
+                let offset_xy = Vec2::from(offset);
+                if offset.magnitude() > ATTACK_RANGE
+                    || offset_xy.try_normalized().map(|dir| dir.dot(facing)).unwrap_or(1.0) < ATTACK_ARC_COS
+                {
+                    continue;
+                }
+
+                damages.0.push(Damage {
+                    target,
+                    amount: ATTACK_DAMAGE,
+                    source: Some(attacker),
+                });
+
+                target_vel.0 += Vec3::from(facing * KNOCKBACK_SPEED) + Vec3::unit_z() * KNOCKBACK_LIFT;
+                // Players simulate their own physics, so make sure they find out about the knockback
+                let _ = force_updates.insert(target, ForceUpdate);
+            }
+        }
+    }
+}
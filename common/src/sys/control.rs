@@ -3,7 +3,23 @@ use specs::{Join, Read, ReadStorage, System, WriteStorage, Entities};
 use vek::*;
 
 // Crate
-use crate::comp::{Control, Animation, AnimationHistory, phys::{Pos, Vel, Dir}};
+use crate::{
+    comp::{
+        Control,
+        Gait,
+        MovementStats,
+        Stats,
+        Animation,
+        AnimationHistory,
+        Attacking,
+        Mount,
+        Rider,
+        combat::ATTACK_DURATION,
+        phys::{Pos, Vel, Dir, OnGround},
+    },
+    state::{DeltaTime, Uid},
+    sys::mount::mounts_by_uid,
+};
 
 // Basic ECS AI agent system
 pub struct Sys;
@@ -11,21 +27,119 @@ pub struct Sys;
 impl<'a> System<'a> for Sys {
     type SystemData = (
         Entities<'a>,
+        Read<'a, DeltaTime>,
         WriteStorage<'a, Vel>,
         WriteStorage<'a, Dir>,
         WriteStorage<'a, AnimationHistory>,
-        ReadStorage<'a, Control>,
+        WriteStorage<'a, Control>,
+        ReadStorage<'a, Uid>,
+        ReadStorage<'a, Mount>,
+        ReadStorage<'a, Rider>,
+        ReadStorage<'a, OnGround>,
+        ReadStorage<'a, MovementStats>,
+        ReadStorage<'a, Stats>,
+        WriteStorage<'a, Attacking>,
     );
 
-    fn run(&mut self, (entities, mut vels, mut dirs, mut anims, controls): Self::SystemData) {
-        for (entity, mut vel, mut dir, control) in (&entities, &mut vels, &mut dirs, &controls).join() {
-            // TODO: Don't hard-code this
-            // Apply physics to the player: acceleration and non-linear decceleration
-            vel.0 += control.move_dir * 2.0 - vel.0.map(|e| e * e.abs() + e) * 0.03;
+    fn run(&mut self, (entities, dt, mut vels, mut dirs, mut anims, mut controls, uids, mounts, riders, on_grounds, movement_stats, vital_stats, mut attackings): Self::SystemData) {
+        // Advance attacks in progress, finishing those that have gone on long enough
+        let finished_attacks = (&entities, &mut attackings)
+            .join()
+            .filter_map(|(entity, attacking)| {
+                attacking.time += dt.0 as f32;
+                if attacking.time > ATTACK_DURATION { Some(entity) } else { None }
+            })
+            .collect::<Vec<_>>();
+        for entity in finished_attacks {
+            attackings.remove(entity);
+        }
+
+        // Riders steer their mounts instead of moving themselves
+        let mounts = mounts_by_uid(&entities, &uids, &mounts);
+        let steering = (&entities, &riders, &controls)
+            .join()
+            .map(|(entity, rider, control)| (entity, rider.mount, *control))
+            .collect::<Vec<_>>();
+        for (entity, mount_uid, control) in steering {
+            let last_animation = anims.get_mut(entity).map(|h| h.current);
+            anims.insert(entity, AnimationHistory {
+                last: last_animation,
+                current: Animation::Idle,
+            });
+
+            if let Some(mount) = mounts.get(&mount_uid) {
+                let _ = controls.insert(*mount, control);
+            }
+        }
+
+        for (entity, mut vel, mut dir, control, on_ground, stats, vital_stats, _) in (
+            &entities,
+            &mut vels,
+            &mut dirs,
+            &controls,
+            on_grounds.maybe(),
+            movement_stats.maybe(),
+            vital_stats.maybe(),
+            !&riders,
+        ).join() {
+            // The dead can't move, they just fall where they are
+            if vital_stats.map(|s| s.is_dead()).unwrap_or(false) {
+                vel.0.x = 0.0;
+                vel.0.y = 0.0;
+
+                let last_animation = anims.get_mut(entity).map(|h| h.current);
+                anims.insert(entity, AnimationHistory {
+                    last: last_animation,
+                    current: Animation::Death,
+                });
+                continue;
+            }
+
+            // Only one attack can be in progress at a time
+            if control.attack && attackings.get(entity).is_none() {
+                let _ = attackings.insert(entity, Attacking::default());
+            }
+
+            let stats = stats.cloned().unwrap_or_default();
+            let on_ground = on_ground.is_some();
+            let gliding = control.glide && !on_ground;
+
+            let base_accel = if on_ground {
+                stats.accel
+            } else if gliding {
+                stats.glide_accel
+            } else {
+                stats.air_accel
+            };
+            let accel = base_accel * match control.gait {
+                Gait::Walk => stats.walk_factor,
+                Gait::Run => 1.0,
+                Gait::Sprint => stats.sprint_factor,
+            };
+
+            // Apply physics to the player: acceleration and non-linear decceleration. Vertical
+            // movement is left to the physics system.
+            let drag = vel.0.map(|e| e * e.abs() + e) * stats.drag;
+            vel.0 += Vec3::from(control.move_dir * accel) - Vec3::new(drag.x, drag.y, 0.0);
+
+            if on_ground && control.jump {
+                vel.0.z = stats.jump_speed;
+            } else if gliding {
+                vel.0.z = vel.0.z.max(-stats.glide_fall_speed);
+            }
+
+            if control.move_dir.magnitude() > 0.01 {
+                dir.0 = (vel.0 * Vec3::new(1.0, 1.0, 0.0)).normalized();
+            }
 
             let animation =
-                if control.move_dir.magnitude() > 0.01 {
-                    dir.0 = vel.0.normalized() * Vec3::new(1.0, 1.0, 0.0);
+                if attackings.get(entity).is_some() {
+                    Animation::Attack
+                } else if gliding {
+                    Animation::Glide
+                } else if !on_ground {
+                    Animation::Jump
+                } else if control.move_dir.magnitude() > 0.01 {
                     Animation::Run
                 } else {
                     Animation::Idle
@@ -34,7 +148,7 @@ impl<'a> System<'a> for Sys {
             let last_animation = anims.get_mut(entity).map(|h| h.current);
 
             anims.insert(entity, AnimationHistory {
-                last: last_animation, 
+                last: last_animation,
                 current: animation,
             });
         }
@@ -1,17 +1,26 @@
 pub mod agent;
+pub mod combat;
 pub mod control;
+pub mod mount;
 pub mod phys;
+pub mod stats;
 
-// External
-use specs::DispatcherBuilder;
+// Crate
+use crate::state::State;
 
 // System names
-const AGENT_SYS: &str = "agent_sys";
-const CONTROL_SYS: &str = "control_sys";
-const MOVEMENT_SYS: &str = "movement_sys";
+pub const AGENT_SYS: &str = "agent_sys";
+pub const CONTROL_SYS: &str = "control_sys";
+pub const MOVEMENT_SYS: &str = "movement_sys";
+pub const MOUNT_SYS: &str = "mount_sys";
+pub const STATS_SYS: &str = "stats_sys";
+pub const COMBAT_SYS: &str = "combat_sys";
 
-pub fn add_local_systems(dispatch_builder: &mut DispatcherBuilder) {
-    dispatch_builder.add(agent::Sys, AGENT_SYS, &[]);
-    dispatch_builder.add(control::Sys, CONTROL_SYS, &[]);
-    dispatch_builder.add(phys::Sys, MOVEMENT_SYS, &[]);
+/// Add the systems shared by the client and the server to the state. Agents are only driven by
+/// the server, so `agent::Sys` isn't among them. The control system runs after the systems named
+/// in `control_deps`, which must already have been added.
+pub fn add_local_systems(state: &mut State, control_deps: &[&str]) {
+    state.add_system(control::Sys, CONTROL_SYS, control_deps);
+    state.add_system(phys::Sys, MOVEMENT_SYS, &[CONTROL_SYS]);
+    state.add_system(mount::Sys, MOUNT_SYS, &[MOVEMENT_SYS]);
 }
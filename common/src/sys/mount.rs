@@ -0,0 +1,171 @@
+// Standard
+use std::collections::HashMap;
+
+// Library
+use specs::{Entities, Entity, Join, ReadStorage, System, WriteStorage};
+use vek::*;
+
+// Crate
+use crate::{
+    comp::{
+        Body,
+        Mount,
+        Rider,
+        phys::{Pos, Vel, Dir},
+    },
+    state::Uid,
+};
+
+/// Map the `Uid`s of the entities being ridden to the entities themselves, so that riders can
+/// find their mounts. Systems can't use `World::entity_from_uid`, and only mounts are looked at,
+/// so this stays cheap however many other entities there are.
+pub fn mounts_by_uid(
+    entities: &Entities,
+    uids: &ReadStorage<Uid>,
+    mounts: &ReadStorage<Mount>,
+) -> HashMap<u64, Entity> {
+    (entities, uids, mounts)
+        .join()
+        .map(|(entity, uid, _)| ((*uid).into(), entity))
+        .collect()
+}
+
+/// Keeps riders seated on their mounts. This runs after movement, so riders end up exactly where
+/// their mount moved to this tick.
+pub struct Sys;
+
+impl<'a> System<'a> for Sys {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Uid>,
+        ReadStorage<'a, Mount>,
+        ReadStorage<'a, Rider>,
+        ReadStorage<'a, Body>,
+        WriteStorage<'a, Pos>,
+        WriteStorage<'a, Vel>,
+        WriteStorage<'a, Dir>,
+    );
+
+    fn run(&mut self, (entities, uids, mounts, riders, bodies, mut positions, mut vels, mut dirs): Self::SystemData) {
+        let mounts = mounts_by_uid(&entities, &uids, &mounts);
+        let seats = (&entities, &riders)
+            .join()
+            .filter_map(|(entity, rider)| {
+                let mount = *mounts.get(&rider.mount)?;
+                let offset = bodies.get(mount)?.mount_offset()?;
+                let pos = positions.get(mount)?.0;
+                let vel = vels.get(mount).map(|v| v.0).unwrap_or(Vec3::zero());
+                let dir = dirs.get(mount).map(|d| d.0).unwrap_or(Vec3::unit_x());
+
+                // Turn the offset to face the same way as the mount
+                let facing = Vec2::<f32>::from(dir).try_normalized().unwrap_or(Vec2::unit_x());
+                let offset = Vec3::new(
+                    offset.x * facing.x - offset.y * facing.y,
+                    offset.x * facing.y + offset.y * facing.x,
+                    offset.z,
+                );
+
+                Some((entity, pos + offset, vel, dir))
+            })
+            .collect::<Vec<_>>();
+
+        for (entity, pos, vel, dir) in seats {
+            let _ = positions.insert(entity, Pos(pos));
+            let _ = vels.insert(entity, Vel(vel));
+            let _ = dirs.insert(entity, Dir(dir));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use specs::Builder;
+    use super::*;
+    use crate::{
+        comp::{Control, Mount, body::quadruped},
+        state::State,
+        terrain::{Block, TerrainChunk, TerrainChunkMeta},
+        vol::WriteVol,
+    };
+
+    /// A chunk with a flat stone floor with its top surface at `floor_height`
+    fn flat_chunk(floor_height: i32) -> TerrainChunk {
+        let mut chunk = TerrainChunk::filled(Block::empty(), TerrainChunkMeta::void());
+        let stone = Block::new(1, Rgb::new(200, 220, 255));
+        for x in 0..32 {
+            for y in 0..32 {
+                for z in 0..floor_height {
+                    chunk.set(Vec3::new(x, y, z), stone).unwrap();
+                }
+            }
+        }
+        chunk
+    }
+
+    /// A player on the back of a boar, both standing on flat ground. Returns the player and the
+    /// boar.
+    fn riding(state: &mut State) -> (Entity, Entity) {
+        state.insert_chunk(Vec3::zero(), flat_chunk(4));
+        let mount = state
+            .ecs_mut()
+            .create_entity_synced()
+            .with(Body::Quadruped(quadruped::Body::new(quadruped::Species::Boar)))
+            .with(Pos(Vec3::new(8.0, 16.0, 4.0)))
+            .with(Vel(Vec3::zero()))
+            .with(Dir(Vec3::unit_x()))
+            .with(Control::default())
+            .build();
+        let rider = state
+            .ecs_mut()
+            .create_entity_synced()
+            .with(Pos(Vec3::new(10.0, 10.0, 4.0)))
+            .with(Vel(Vec3::zero()))
+            .with(Dir(Vec3::unit_y()))
+            .with(Control {
+                move_dir: Vec2::unit_x(),
+                ..Control::default()
+            })
+            .build();
+
+        let mount_uid = state.ecs().uid_from_entity(mount).unwrap().into();
+        let rider_uid = state.ecs().uid_from_entity(rider).unwrap().into();
+        state.write_component(mount, Mount { rider: rider_uid });
+        state.write_component(rider, Rider { mount: mount_uid });
+        (rider, mount)
+    }
+
+    fn run_ticks(state: &mut State, ticks: usize) {
+        for _ in 0..ticks {
+            state.tick(Duration::from_millis(33));
+            state.cleanup();
+        }
+    }
+
+    #[test]
+    fn riders_steer_their_mounts() {
+        let mut state = State::new();
+        let (_, mount) = riding(&mut state);
+
+        run_ticks(&mut state, 1);
+
+        let control = state.read_component_cloned::<Control>(mount).unwrap();
+        assert_eq!(control.move_dir, Vec2::unit_x());
+    }
+
+    #[test]
+    fn riders_stay_in_the_saddle() {
+        let mut state = State::new();
+        let (rider, mount) = riding(&mut state);
+
+        run_ticks(&mut state, 30);
+
+        let mount_pos = state.read_component_cloned::<Pos>(mount).unwrap().0;
+        let rider_pos = state.read_component_cloned::<Pos>(rider).unwrap().0;
+        // The mount was steered forwards, and the rider came along
+        assert!(mount_pos.x > 9.0);
+        assert!(rider_pos.distance(mount_pos + quadruped::SADDLE_OFFSET) < 0.001);
+        let dir = state.read_component_cloned::<Dir>(rider).unwrap().0;
+        assert!(dir.distance(Vec3::unit_x()) < 0.001);
+    }
+}
@@ -1,25 +1,222 @@
 // Library
-use specs::{Join, Read, ReadStorage, System, WriteStorage};
+use specs::{Entities, Join, Read, ReadExpect, System, WriteStorage};
+use vek::*;
 
 // Crate
 use crate::{
-    comp::phys::{Pos, Vel},
+    comp::phys::{Pos, Vel, OnGround},
     state::DeltaTime,
+    terrain::TerrainMap,
+    vol::{Vox, ReadVol},
 };
 
+// TODO: Don't hard-code these
+const GRAVITY: f32 = 9.81 * 4.0;
+const MAX_FALL_SPEED: f32 = 50.0;
+// The size of an entity's collision box. The box is centred on the entity's position in the XY
+// plane, with the entity's position at the bottom.
+const BODY_RADIUS: f32 = 0.4;
+const BODY_HEIGHT: f32 = 1.7;
+// Entities never move more than this far in one go, so they can't tunnel through blocks
+const MAX_SUBSTEP: f32 = 0.25;
+const GROUND_EPSILON: f32 = 0.01;
+
+/// Returns true if an entity at the provided position would intersect solid terrain.
+///
+/// Blocks in chunks that aren't loaded are considered solid, since we can't know otherwise.
+fn collides(terrain: &TerrainMap, pos: Vec3<f32>) -> bool {
+    let min = (pos - Vec3::new(BODY_RADIUS, BODY_RADIUS, 0.0)).map(|e| e.floor() as i32);
+    let max = (pos + Vec3::new(BODY_RADIUS, BODY_RADIUS, BODY_HEIGHT)).map(|e| e.ceil() as i32);
+
+    for x in min.x..max.x {
+        for y in min.y..max.y {
+            for z in min.z..max.z {
+                match terrain.get(Vec3::new(x, y, z)) {
+                    Ok(block) if block.is_empty() => {},
+                    _ => return true,
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Returns true if an entity at the provided position is standing on solid terrain.
+pub fn on_ground(terrain: &TerrainMap, pos: Vec3<f32>) -> bool {
+    collides(terrain, pos - Vec3::unit_z() * GROUND_EPSILON)
+}
+
 // Basic ECS physics system
 pub struct Sys;
 
 impl<'a> System<'a> for Sys {
     type SystemData = (
-        WriteStorage<'a, Pos>,
-        ReadStorage<'a, Vel>,
+        Entities<'a>,
+        ReadExpect<'a, TerrainMap>,
         Read<'a, DeltaTime>,
+        WriteStorage<'a, Pos>,
+        WriteStorage<'a, Vel>,
+        WriteStorage<'a, OnGround>,
     );
 
-    fn run(&mut self, (mut positions, velocities, dt): Self::SystemData) {
-        (&mut positions, &velocities)
-            .join() // this can be parallelized with par_join()
-            .for_each(|(pos, vel)| pos.0 += vel.0 * dt.0 as f32);
+    fn run(&mut self, (entities, terrain, dt, mut positions, mut velocities, mut on_grounds): Self::SystemData) {
+        for (entity, pos, vel) in (&entities, &mut positions, &mut velocities).join() {
+            // Don't simulate entities in chunks that haven't been loaded yet. Otherwise, they'd
+            // fall straight through the terrain that's about to appear beneath them.
+            if terrain.get(pos.0.map(|e| e.floor() as i32)).is_err() {
+                continue;
+            }
+
+            let was_on_ground = on_ground(&terrain, pos.0);
+
+            // Gravity
+            vel.0.z = (vel.0.z - GRAVITY * dt.0 as f32).max(-MAX_FALL_SPEED);
+
+            // Move one axis at a time, in small substeps, resolving collisions as we go
+            let displacement = vel.0 * dt.0 as f32;
+            let substeps = (displacement.map(|e| e.abs()).reduce_partial_max() / MAX_SUBSTEP).ceil().max(1.0);
+            let substep = displacement / substeps;
+
+            for _ in 0..substeps as usize {
+                // Vertical
+                let new_pos = pos.0 + Vec3::unit_z() * substep.z;
+                if !collides(&terrain, new_pos) {
+                    pos.0 = new_pos;
+                } else {
+                    // Snap to the top of the block we landed on, if possible
+                    if substep.z < 0.0 {
+                        let snapped = Vec3::new(pos.0.x, pos.0.y, new_pos.z.floor() + 1.0);
+                        if !collides(&terrain, snapped) {
+                            pos.0 = snapped;
+                        }
+                    }
+                    vel.0.z = 0.0;
+                }
+
+                // Horizontal
+                for axis in &[Vec3::unit_x(), Vec3::unit_y()] {
+                    let new_pos = pos.0 + *axis * substep;
+                    if !collides(&terrain, new_pos) {
+                        pos.0 = new_pos;
+                        continue;
+                    }
+
+                    // Step up onto single blocks, if there's room to do so
+                    let stepped = Vec3::new(new_pos.x, new_pos.y, new_pos.z.floor() + 1.0);
+                    if was_on_ground
+                        && stepped.z - pos.0.z <= 1.0
+                        && !collides(&terrain, Vec3::new(pos.0.x, pos.0.y, stepped.z))
+                        && !collides(&terrain, stepped)
+                    {
+                        pos.0 = stepped;
+                    } else {
+                        vel.0 *= Vec3::one() - *axis;
+                    }
+                }
+            }
+
+            if on_ground(&terrain, pos.0) {
+                let _ = on_grounds.insert(entity, OnGround);
+            } else {
+                on_grounds.remove(entity);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use specs::Builder;
+    use super::*;
+    use crate::{
+        comp::phys::Dir,
+        state::State,
+        terrain::{Block, TerrainChunk, TerrainChunkMeta},
+        vol::WriteVol,
+    };
+
+    /// A chunk with a flat stone floor with its top surface at `floor_height`
+    fn flat_chunk(floor_height: i32) -> TerrainChunk {
+        let mut chunk = TerrainChunk::filled(Block::empty(), TerrainChunkMeta::void());
+        let stone = Block::new(1, Rgb::new(200, 220, 255));
+        for x in 0..32 {
+            for y in 0..32 {
+                for z in 0..floor_height {
+                    chunk.set(Vec3::new(x, y, z), stone).unwrap();
+                }
+            }
+        }
+        chunk
+    }
+
+    fn spawn(state: &mut State, pos: Vec3<f32>, vel: Vec3<f32>) -> specs::Entity {
+        state
+            .ecs_mut()
+            .internal_mut()
+            .create_entity()
+            .with(Pos(pos))
+            .with(Vel(vel))
+            .with(Dir(Vec3::unit_y()))
+            .build()
+    }
+
+    fn run_ticks(state: &mut State, ticks: usize) {
+        for _ in 0..ticks {
+            state.tick(Duration::from_millis(33));
+            state.cleanup();
+        }
+    }
+
+    #[test]
+    fn falls_onto_the_ground() {
+        let mut state = State::new();
+        state.insert_chunk(Vec3::zero(), flat_chunk(4));
+        let entity = spawn(&mut state, Vec3::new(16.0, 16.0, 20.0), Vec3::zero());
+
+        run_ticks(&mut state, 100);
+
+        let pos = state.read_component_cloned::<Pos>(entity).unwrap().0;
+        assert!((pos.z - 4.0).abs() < 0.001);
+        assert!(state.read_storage::<OnGround>().get(entity).is_some());
+    }
+
+    #[test]
+    fn frozen_until_chunk_is_loaded() {
+        let mut state = State::new();
+        let entity = spawn(&mut state, Vec3::new(16.0, 16.0, 20.0), Vec3::zero());
+
+        run_ticks(&mut state, 10);
+
+        let pos = state.read_component_cloned::<Pos>(entity).unwrap().0;
+        assert_eq!(pos, Vec3::new(16.0, 16.0, 20.0));
+    }
+
+    #[test]
+    fn steps_up_single_blocks_but_not_walls() {
+        let mut state = State::new();
+        let mut chunk = flat_chunk(4);
+        let stone = Block::new(1, Rgb::new(200, 220, 255));
+        for y in 0..32 {
+            // A one block high ledge
+            chunk.set(Vec3::new(10, y, 4), stone).unwrap();
+            // A two block high wall standing on the ledge
+            for z in 5..7 {
+                chunk.set(Vec3::new(20, y, z), stone).unwrap();
+            }
+        }
+        for x in 10..20 {
+            for y in 0..32 {
+                chunk.set(Vec3::new(x, y, 4), stone).unwrap();
+            }
+        }
+        state.insert_chunk(Vec3::zero(), chunk);
+        let entity = spawn(&mut state, Vec3::new(5.0, 16.0, 4.0), Vec3::new(4.0, 0.0, 0.0));
+
+        run_ticks(&mut state, 150);
+
+        let pos = state.read_component_cloned::<Pos>(entity).unwrap().0;
+        assert!((pos.z - 5.0).abs() < 0.001);
+        assert!(pos.x > 10.0 && pos.x < 20.0);
     }
 }
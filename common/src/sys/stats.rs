@@ -0,0 +1,48 @@
+// Library
+use specs::{Entities, Join, Read, System, WriteStorage};
+
+// Crate
+use crate::{
+    comp::Stats,
+    state::DeltaTime,
+};
+
+// Health is regenerated in whole points, so only do it this often to avoid syncing stats every tick
+const REGEN_INTERVAL: f64 = 1.0;
+
+/// Regenerates the health of living entities. This only runs on the server, since stats are
+/// synced to clients.
+#[derive(Default)]
+pub struct Sys {
+    timer: f64,
+}
+
+impl<'a> System<'a> for Sys {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, DeltaTime>,
+        WriteStorage<'a, Stats>,
+    );
+
+    fn run(&mut self, (entities, dt, mut stats): Self::SystemData) {
+        self.timer += dt.0;
+        if self.timer < REGEN_INTERVAL {
+            return;
+        }
+        self.timer -= REGEN_INTERVAL;
+
+        // Stats use flagged storage (and so are synced whenever they're mutably accessed), so only
+        // touch those that actually change
+        let injured = (&entities, &stats)
+            .join()
+            .filter(|(_, stats)| !stats.is_dead() && stats.health < stats.max_health)
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+
+        for entity in injured {
+            if let Some(stats) = stats.get_mut(entity) {
+                stats.health = (stats.health + stats.health_regen).min(stats.max_health);
+            }
+        }
+    }
+}
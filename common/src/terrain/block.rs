@@ -4,7 +4,7 @@ use serde_derive::{Serialize, Deserialize};
 // Crate
 use crate::vol::Vox;
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Block {
     kind: u8,
     color: [u8; 3],
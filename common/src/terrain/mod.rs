@@ -13,7 +13,7 @@ use crate::{
     vol::VolSize,
     volumes::{
         vol_map::VolMap,
-        chunk::Chunk,
+        chunk::{Chunk, ChunkDelta},
     },
 };
 
@@ -34,14 +34,21 @@ pub struct TerrainChunkMeta {
 }
 
 impl TerrainChunkMeta {
+    pub fn new(biome: BiomeKind) -> Self {
+        Self { biome }
+    }
+
     pub fn void() -> Self {
-        Self {
-            biome: BiomeKind::Void,
-        }
+        Self::new(BiomeKind::Void)
+    }
+
+    pub fn biome(&self) -> BiomeKind {
+        self.biome
     }
 }
 
 // Terrain type aliases
 
 pub type TerrainChunk = Chunk<Block, TerrainChunkSize, TerrainChunkMeta>;
+pub type TerrainChunkDelta = ChunkDelta<Block>;
 pub type TerrainMap = VolMap<Block, TerrainChunkSize, TerrainChunkMeta>;
@@ -1,6 +1,9 @@
 // Library
 use vek::*;
 
+// Crate
+use crate::ray::{Ray, is_solid};
+
 /// A voxel
 pub trait Vox {
     fn empty() -> Self;
@@ -64,6 +67,14 @@ pub trait ReadVol: BaseVol {
     /// Get a reference to the voxel at the provided position in the volume.
     #[inline(always)]
     fn get(&self, pos: Vec3<i32>) -> Result<&Self::Vox, Self::Err>;
+
+    /// Cast a ray through the volume from `from` to `to`. By default, the ray stops at the first
+    /// non-empty voxel (see `Ray::until` to change this).
+    fn ray(&self, from: Vec3<f32>, to: Vec3<f32>) -> Ray<Self, fn(&Self::Vox) -> bool>
+        where Self: Sized
+    {
+        Ray::new(self, from, to, is_solid::<Self::Vox>)
+    }
 }
 
 /// A volume that provides the ability to sample (i.e: clone a section of) its voxel data.
@@ -1,18 +1,21 @@
 // Standard
-use std::marker::PhantomData;
+use std::{hash::Hash, marker::PhantomData};
 
 // Library
 use vek::*;
 use serde_derive::{Serialize, Deserialize};
 
 // Local
-use crate::vol::{
-    Vox,
-    BaseVol,
-    SizedVol,
-    ReadVol,
-    WriteVol,
-    VolSize,
+use crate::{
+    vol::{
+        Vox,
+        BaseVol,
+        SizedVol,
+        ReadVol,
+        WriteVol,
+        VolSize,
+    },
+    volumes::palette::PaletteVec,
 };
 
 #[derive(Debug)]
@@ -20,18 +23,64 @@ pub enum ChunkErr {
     OutOfBounds,
 }
 
+/// A compact description of changes made to a `Chunk`. Changed voxels are stored as runs of
+/// consecutive voxels (in index order) along with the index at which each run starts.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChunkDelta<V> {
+    runs: Vec<(u32, Vec<V>)>,
+}
+
+impl<V: Clone> ChunkDelta<V> {
+    /// Build a delta from the provided voxel array, containing only the voxels at the provided
+    /// (sorted and deduplicated) indices.
+    fn from_indices<I: IntoIterator<Item = usize>>(vox: &PaletteVec<V>, idxs: I) -> Self {
+        let mut runs: Vec<(u32, Vec<V>)> = Vec::new();
+        for idx in idxs {
+            let v = match vox.get(idx) {
+                Some(v) => v.clone(),
+                None => continue,
+            };
+            match runs.last_mut() {
+                Some((start, run)) if *start as usize + run.len() == idx => run.push(v),
+                _ => runs.push((idx as u32, vec![v])),
+            }
+        }
+        Self { runs }
+    }
+
+    /// Get the number of voxels changed by this delta.
+    pub fn len(&self) -> usize {
+        self.runs.iter().map(|(_, run)| run.len()).sum()
+    }
+
+    /// Returns true if this delta doesn't change any voxels.
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+}
+
 /// A volume with dimensions known at compile-time.
+///
+/// Voxels are stored in a palette-compressed form (see `PaletteVec`), so chunks made up of only a
+/// few distinct voxels (such as chunks entirely filled with air or stone) take up very little
+/// memory.
 // V = Voxel
 // S = Size (replace when const generics are a thing)
 // M = Metadata
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Chunk<V: Vox, S: VolSize, M> {
-    vox: Vec<V>,
+    vox: PaletteVec<V>,
     meta: M,
     phantom: PhantomData<S>,
 }
 
 impl<V: Vox, S: VolSize, M> Chunk<V, S, M> {
+    /// Get a lower bound on the number of bytes needed to serialize the chunk, which is much
+    /// cheaper to find than the exact size.
+    pub fn min_serialized_size(&self) -> usize {
+        self.vox.packed_size()
+    }
+
     /// Used to transform a voxel position in the volume into its corresponding index in the voxel
     // array.
     #[inline(always)]
@@ -70,12 +119,11 @@ impl<V: Vox, S: VolSize, M> ReadVol for Chunk<V, S, M> {
     }
 }
 
-impl<V: Vox, S: VolSize, M> WriteVol for Chunk<V, S, M> {
+impl<V: Vox + Clone + Eq + Hash, S: VolSize, M> WriteVol for Chunk<V, S, M> {
     #[inline(always)]
     fn set(&mut self, pos: Vec3<i32>, vox: Self::Vox) -> Result<(), ChunkErr> {
         Self::idx_for(pos)
-            .and_then(|idx| self.vox.get_mut(idx))
-            .map(|old_vox| *old_vox = vox)
+            .and_then(|idx| self.vox.set(idx, vox))
             .ok_or(ChunkErr::OutOfBounds)
     }
 }
@@ -85,7 +133,7 @@ impl<V: Vox + Clone, S: VolSize, M> Chunk<V, S, M> {
     /// the provided voxel.
     pub fn filled(vox: V, meta: M) -> Self {
         Self {
-            vox: vec![vox; S::SIZE.product() as usize],
+            vox: PaletteVec::filled(S::SIZE.product() as usize, vox),
             meta,
             phantom: PhantomData,
         }
@@ -100,4 +148,100 @@ impl<V: Vox + Clone, S: VolSize, M> Chunk<V, S, M> {
     pub fn metadata_mut(&mut self) -> &mut M {
         &mut self.meta
     }
+
+    /// Create a delta containing the current values of the voxels at the provided positions.
+    pub fn delta_for<I: IntoIterator<Item = Vec3<i32>>>(&self, positions: I) -> Result<ChunkDelta<V>, ChunkErr> {
+        let mut idxs = positions
+            .into_iter()
+            .map(|pos| Self::idx_for(pos).ok_or(ChunkErr::OutOfBounds))
+            .collect::<Result<Vec<_>, _>>()?;
+        idxs.sort_unstable();
+        idxs.dedup();
+
+        Ok(ChunkDelta::from_indices(&self.vox, idxs))
+    }
+
+    /// Create a delta that, when applied to `old`, produces this chunk.
+    pub fn diff(&self, old: &Self) -> ChunkDelta<V> where V: PartialEq {
+        ChunkDelta::from_indices(
+            &self.vox,
+            (0..self.vox.len()).filter(|idx| self.vox.get(*idx) != old.vox.get(*idx)),
+        )
+    }
+
+    /// Apply the changes described by a delta to this chunk.
+    pub fn apply_delta(&mut self, delta: &ChunkDelta<V>) -> Result<(), ChunkErr> where V: Eq + Hash {
+        for (start, run) in &delta.runs {
+            for (i, v) in run.iter().enumerate() {
+                self.vox
+                    .set(*start as usize + i, v.clone())
+                    .ok_or(ChunkErr::OutOfBounds)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Release memory used by voxel kinds that no longer appear in the chunk.
+    pub fn shrink(&mut self) where V: Eq + Hash {
+        self.vox.shrink();
+    }
+
+    /// Returns true if the chunk's palette has a single entry, so every voxel is the same. Chunks
+    /// that have become uniform through edits only report it after `shrink`.
+    pub fn is_uniform(&self) -> bool {
+        self.vox.is_uniform()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestSize;
+
+    impl VolSize for TestSize {
+        const SIZE: Vec3<u32> = Vec3 { x: 4, y: 4, z: 4 };
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+    struct TestVox(u8);
+
+    impl Vox for TestVox {
+        fn empty() -> Self { TestVox(0) }
+        fn is_empty(&self) -> bool { self.0 == 0 }
+    }
+
+    type TestChunk = Chunk<TestVox, TestSize, ()>;
+
+    #[test]
+    fn delta_merges_consecutive_voxels_into_runs() {
+        let mut chunk = TestChunk::filled(TestVox(0), ());
+        for z in 0..3 {
+            chunk.set(Vec3::new(1, 2, z), TestVox(1)).unwrap();
+        }
+        chunk.set(Vec3::new(3, 3, 3), TestVox(2)).unwrap();
+
+        let delta = chunk
+            .delta_for(vec![Vec3::new(3, 3, 3), Vec3::new(1, 2, 2), Vec3::new(1, 2, 0), Vec3::new(1, 2, 1)])
+            .unwrap();
+
+        assert_eq!(delta.runs.len(), 2);
+        assert_eq!(delta.len(), 4);
+    }
+
+    #[test]
+    fn diff_then_apply_reproduces_chunk() {
+        let old = TestChunk::filled(TestVox(0), ());
+        let mut new = old.clone();
+        new.set(Vec3::new(0, 0, 0), TestVox(3)).unwrap();
+        new.set(Vec3::new(2, 1, 3), TestVox(4)).unwrap();
+
+        let mut patched = old.clone();
+        patched.apply_delta(&new.diff(&old)).unwrap();
+
+        for pos in new.iter_positions() {
+            assert_eq!(patched.get(pos).unwrap(), new.get(pos).unwrap());
+        }
+        assert!(new.diff(&new).is_empty());
+    }
 }
@@ -1,3 +1,4 @@
 pub mod dyna;
 pub mod chunk;
 pub mod vol_map;
+pub mod palette;
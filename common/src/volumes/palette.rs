@@ -0,0 +1,264 @@
+// Standard
+use std::{collections::HashMap, hash::Hash};
+
+// Library
+use serde_derive::{Serialize, Deserialize};
+
+// Palettes with more entries than this are searched through a hash map rather than linearly
+const LINEAR_SEARCH_LEN: usize = 16;
+
+/// A fixed-length array of values stored as indices into a palette of distinct values.
+///
+/// Indices are bit-packed using the fewest bits (a power of two) needed to address the palette.
+/// When the palette has a single entry (i.e: the array is uniform), no indices are stored at all.
+/// Unused palette entries are dropped before the indices are widened, and by `shrink`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PaletteVec<V> {
+    len: usize,
+    palette: Vec<V>,
+    // The number of bits used to store each index. 0 when the array is uniform.
+    bits: u32,
+    indices: Vec<u64>,
+    // The index of each palette entry, for large palettes. Empty until needed, and rebuilt after
+    // deserialization or `shrink`.
+    #[serde(skip)]
+    lookup: HashMap<V, usize>,
+}
+
+impl<V> PaletteVec<V> {
+    /// Get the number of bits needed to address a palette with the provided number of entries.
+    /// This is always a power of two (or 0) so that indices never straddle words.
+    fn bits_for(palette_len: usize) -> u32 {
+        match palette_len {
+            0..=1 => 0,
+            2 => 1,
+            3..=4 => 2,
+            5..=16 => 4,
+            17..=256 => 8,
+            257..=65536 => 16,
+            _ => 32,
+        }
+    }
+
+    fn words_for(len: usize, bits: u32) -> usize {
+        (len * bits as usize + 63) / 64
+    }
+
+    #[inline(always)]
+    fn read_index(indices: &[u64], bits: u32, i: usize) -> usize {
+        if bits == 0 {
+            0
+        } else {
+            let bit = i * bits as usize;
+            let mask = (1u64 << bits) - 1;
+            ((indices[bit / 64] >> (bit % 64)) & mask) as usize
+        }
+    }
+
+    #[inline(always)]
+    fn write_index(indices: &mut [u64], bits: u32, i: usize, palette_idx: usize) {
+        let bit = i * bits as usize;
+        let mask = (1u64 << bits) - 1;
+        let word = &mut indices[bit / 64];
+        *word = (*word & !(mask << (bit % 64))) | ((palette_idx as u64 & mask) << (bit % 64));
+    }
+
+    /// Re-encode the indices using a different number of bits per index.
+    fn repack(&mut self, new_bits: u32) {
+        let mut new_indices = vec![0; Self::words_for(self.len, new_bits)];
+        if new_bits > 0 {
+            for i in 0..self.len {
+                let palette_idx = Self::read_index(&self.indices, self.bits, i);
+                Self::write_index(&mut new_indices, new_bits, i, palette_idx);
+            }
+        }
+        self.bits = new_bits;
+        self.indices = new_indices;
+    }
+
+    /// Get the number of values in the array.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Get the number of distinct values in the palette (which may include values that are no
+    /// longer used, see `shrink`).
+    pub fn palette_len(&self) -> usize {
+        self.palette.len()
+    }
+
+    /// Returns true if the palette has a single entry, in which case every value in the array is
+    /// the same. Arrays that have become uniform only report it once unused palette entries have
+    /// been removed with `shrink`.
+    pub fn is_uniform(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Get the number of bytes taken up by the packed indices. The array can't be serialized in
+    /// fewer bytes than this, and unlike the serialized size it's free to compute.
+    pub fn packed_size(&self) -> usize {
+        self.indices.len() * std::mem::size_of::<u64>()
+    }
+
+    /// Get a reference to the value at the provided index.
+    #[inline(always)]
+    pub fn get(&self, i: usize) -> Option<&V> {
+        if i < self.len {
+            self.palette.get(Self::read_index(&self.indices, self.bits, i))
+        } else {
+            None
+        }
+    }
+}
+
+impl<V: Clone> PaletteVec<V> {
+    /// Create a new `PaletteVec` of the provided length with every value set to `val`.
+    pub fn filled(len: usize, val: V) -> Self {
+        Self {
+            len,
+            palette: vec![val],
+            bits: 0,
+            indices: Vec::new(),
+            lookup: HashMap::new(),
+        }
+    }
+}
+
+impl<V: Clone + Eq + Hash> PaletteVec<V> {
+    /// Find the palette index of a value, if it's in the palette.
+    fn find(&mut self, val: &V) -> Option<usize> {
+        if self.palette.len() <= LINEAR_SEARCH_LEN {
+            return self.palette.iter().position(|v| v == val);
+        }
+        if self.lookup.len() != self.palette.len() {
+            self.lookup = self.palette.iter().cloned().enumerate().map(|(i, v)| (v, i)).collect();
+        }
+        self.lookup.get(val).cloned()
+    }
+
+    /// Set the value at the provided index. Returns `None` if the index is out of bounds.
+    #[inline(always)]
+    pub fn set(&mut self, i: usize, val: V) -> Option<()> {
+        if i >= self.len {
+            return None;
+        }
+
+        let palette_idx = match self.find(&val) {
+            Some(palette_idx) => palette_idx,
+            None => {
+                // Reuse the space of values that are no longer used before widening the indices
+                if Self::bits_for(self.palette.len() + 1) != self.bits {
+                    self.shrink();
+                }
+                if !self.lookup.is_empty() {
+                    self.lookup.insert(val.clone(), self.palette.len());
+                }
+                self.palette.push(val);
+                let new_bits = Self::bits_for(self.palette.len());
+                if new_bits != self.bits {
+                    self.repack(new_bits);
+                }
+                self.palette.len() - 1
+            },
+        };
+
+        // If the array is uniform, the value must already be the only palette entry
+        if self.bits > 0 {
+            Self::write_index(&mut self.indices, self.bits, i, palette_idx);
+        }
+        Some(())
+    }
+
+    /// Remove palette entries that are no longer used, reducing the size of the indices if
+    /// possible.
+    pub fn shrink(&mut self) {
+        let mut used = vec![false; self.palette.len()];
+        for i in 0..self.len {
+            used[Self::read_index(&self.indices, self.bits, i)] = true;
+        }
+
+        if used.iter().all(|used| *used) {
+            return;
+        }
+
+        // Map old palette indices to new ones
+        let mut remap = vec![0; self.palette.len()];
+        let mut next = 0;
+        for (old_idx, used) in used.iter().enumerate() {
+            if *used {
+                remap[old_idx] = next;
+                next += 1;
+            }
+        }
+
+        let new_bits = Self::bits_for(next);
+        let mut new_indices = vec![0; Self::words_for(self.len, new_bits)];
+        if new_bits > 0 {
+            for i in 0..self.len {
+                let old_idx = Self::read_index(&self.indices, self.bits, i);
+                Self::write_index(&mut new_indices, new_bits, i, remap[old_idx]);
+            }
+        }
+
+        let mut old_idx = 0;
+        self.palette.retain(|_| {
+            old_idx += 1;
+            used[old_idx - 1]
+        });
+        self.bits = new_bits;
+        self.indices = new_indices;
+        self.lookup.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniform_until_a_second_value_is_set() {
+        let mut vec = PaletteVec::filled(100, 7u8);
+        assert!(vec.is_uniform());
+        vec.set(42, 7).unwrap();
+        assert!(vec.is_uniform());
+        vec.set(42, 3).unwrap();
+        assert!(!vec.is_uniform());
+        assert_eq!(vec.get(41), Some(&7));
+        assert_eq!(vec.get(42), Some(&3));
+        assert_eq!(vec.get(100), None);
+    }
+
+    #[test]
+    fn values_survive_repacking_and_shrinking() {
+        let mut vec = PaletteVec::filled(1000, 0u32);
+        for i in 0..1000 {
+            vec.set(i, (i % 300) as u32).unwrap();
+        }
+        for i in 0..1000 {
+            assert_eq!(vec.get(i), Some(&((i % 300) as u32)));
+        }
+
+        for i in 0..1000 {
+            vec.set(i, (i % 2) as u32).unwrap();
+        }
+        vec.shrink();
+        assert_eq!(vec.palette_len(), 2);
+        for i in 0..1000 {
+            assert_eq!(vec.get(i), Some(&((i % 2) as u32)));
+        }
+    }
+
+    #[test]
+    fn unused_values_are_dropped_before_widening() {
+        let mut vec = PaletteVec::filled(1000, 0u32);
+        // Keep overwriting the same few values with new ones
+        for n in 0..10_000 {
+            vec.set(n % 4, n as u32 + 1).unwrap();
+        }
+        assert!(vec.palette_len() <= 16);
+        for i in 0..4 {
+            assert_eq!(vec.get(i), Some(&(10_000 - 4 + i as u32 + 1)));
+        }
+        assert_eq!(vec.get(4), Some(&0));
+    }
+}
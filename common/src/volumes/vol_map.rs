@@ -1,5 +1,5 @@
 // Standard
-use std::collections::HashMap;
+use std::{collections::HashMap, hash::Hash};
 
 // Library
 use vek::*;
@@ -100,7 +100,7 @@ impl<V: Vox + Clone, S: VolSize, M> SampleVol for VolMap<V, S, M> {
     }
 }
 
-impl<V: Vox, S: VolSize, M> WriteVol for VolMap<V, S, M> {
+impl<V: Vox + Clone + Eq + Hash, S: VolSize, M> WriteVol for VolMap<V, S, M> {
     #[inline(always)]
     fn set(&mut self, pos: Vec3<i32>, vox: V) -> Result<(), VolMapErr> {
         let ck = Self::chunk_key(pos);
@@ -130,6 +130,10 @@ impl<V: Vox, S: VolSize, M> VolMap<V, S, M> {
         self.chunks.get(&key)
     }
 
+    pub fn get_key_mut(&mut self, key: Vec3<i32>) -> Option<&mut Chunk<V, S, M>> {
+        self.chunks.get_mut(&key)
+    }
+
     pub fn remove(&mut self, key: Vec3<i32>) -> Option<Chunk<V, S, M>> {
         self.chunks.remove(&key)
     }
@@ -0,0 +1,270 @@
+//! # Weather
+//! Weather is simulated by the server on a coarse grid of cells, each covering
+//! `WEATHER_CELL_SIZE`^2 chunk columns. Each cell slowly drifts towards a randomly chosen target
+//! weather, with the likelihood of each kind of weather determined by the cell's `Climate` (as
+//! provided by the world generator). The resulting `WeatherMap` is synced to clients in a
+//! compressed form.
+
+// Standard
+use std::collections::{HashMap, HashSet};
+
+// Library
+use rand::Rng;
+use vek::*;
+
+// Crate
+use crate::terrain::BiomeKind;
+
+/// The number of chunks along each horizontal axis of a weather cell.
+pub const WEATHER_CELL_SIZE: i32 = 8;
+
+/// The fastest wind speed that can be represented, in blocks per second.
+pub const MAX_WIND_SPEED: f32 = 30.0;
+
+// How long (in seconds) it takes a cell to get most of the way to its target weather
+const TRANSITION_TIME: f64 = 60.0;
+// The range of durations (in seconds) between changes of a cell's target weather
+const MIN_TARGET_DURATION: f64 = 120.0;
+const MAX_TARGET_DURATION: f64 = 600.0;
+
+/// The weather at a particular place.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Weather {
+    /// Cloud cover, between 0 (clear skies) and 1 (overcast).
+    pub cloud: f32,
+    /// Rain intensity, between 0 (dry) and 1 (downpour).
+    pub rain: f32,
+    /// Wind velocity in the XY plane, in blocks per second.
+    pub wind: Vec2<f32>,
+}
+
+impl Weather {
+    pub fn new(cloud: f32, rain: f32, wind: Vec2<f32>) -> Self {
+        Self { cloud, rain, wind }
+    }
+
+    /// Linearly interpolate between two weathers.
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        Self {
+            cloud: self.cloud + (other.cloud - self.cloud) * t,
+            rain: self.rain + (other.rain - self.rain) * t,
+            wind: self.wind + (other.wind - self.wind) * t,
+        }
+    }
+
+    pub fn compress(&self) -> CompressedWeather {
+        let quantize = |e: f32| (e.max(0.0).min(1.0) * 255.0).round() as u8;
+        let wind = self.wind.map(|e| (e / MAX_WIND_SPEED).max(-1.0).min(1.0) * 127.0).map(|e| e.round() as i8);
+        CompressedWeather {
+            cloud: quantize(self.cloud),
+            rain: quantize(self.rain),
+            wind: wind.into_array(),
+        }
+    }
+}
+
+/// A compact representation of `Weather` used to sync it over the network.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompressedWeather {
+    cloud: u8,
+    rain: u8,
+    wind: [i8; 2],
+}
+
+impl CompressedWeather {
+    pub fn decompress(&self) -> Weather {
+        Weather {
+            cloud: self.cloud as f32 / 255.0,
+            rain: self.rain as f32 / 255.0,
+            wind: Vec2::from(self.wind).map(|e: i8| e as f32 / 127.0 * MAX_WIND_SPEED),
+        }
+    }
+}
+
+/// The long-term weather tendencies of a region.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Climate {
+    /// How likely the region is to be cloudy or rainy, between 0 and 1.
+    pub humidity: f32,
+    /// How strong the wind in the region tends to be, between 0 and 1.
+    pub windiness: f32,
+}
+
+impl Default for Climate {
+    fn default() -> Self {
+        Self {
+            humidity: 0.5,
+            windiness: 0.3,
+        }
+    }
+}
+
+impl From<BiomeKind> for Climate {
+    fn from(biome: BiomeKind) -> Self {
+        let (humidity, windiness) = match biome {
+            BiomeKind::Void => return Self::default(),
+            BiomeKind::Grassland => (0.5, 0.4),
+            BiomeKind::Ocean => (0.7, 0.7),
+            BiomeKind::Mountain => (0.6, 0.8),
+            BiomeKind::Snowlands => (0.6, 0.6),
+            BiomeKind::Desert => (0.05, 0.5),
+            BiomeKind::Swamp => (0.9, 0.1),
+            BiomeKind::Forest => (0.6, 0.2),
+        };
+        Self { humidity, windiness }
+    }
+}
+
+/// A resource storing the current weather of each simulated cell.
+#[derive(Clone, Debug, Default)]
+pub struct WeatherMap {
+    cells: HashMap<Vec2<i32>, Weather>,
+}
+
+impl WeatherMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the key of the weather cell containing the chunk with the given key.
+    pub fn cell_key(chunk_key: Vec3<i32>) -> Vec2<i32> {
+        Vec2::from(chunk_key).map(|e: i32| e.div_euclid(WEATHER_CELL_SIZE))
+    }
+
+    /// Get the weather of a cell. Cells that aren't being simulated have clear, still weather.
+    pub fn get(&self, cell_key: Vec2<i32>) -> Weather {
+        self.cells.get(&cell_key).cloned().unwrap_or_default()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Vec2<i32>, Weather)> + '_ {
+        self.cells.iter().map(|(key, weather)| (*key, *weather))
+    }
+
+    /// Get a compressed copy of every cell, suitable for sending to clients.
+    pub fn compress(&self) -> Vec<(Vec2<i32>, CompressedWeather)> {
+        self.cells
+            .iter()
+            .map(|(key, weather)| (*key, weather.compress()))
+            .collect()
+    }
+
+    /// Replace the contents of the map with compressed cells received from the server.
+    pub fn apply_compressed(&mut self, cells: Vec<(Vec2<i32>, CompressedWeather)>) {
+        self.cells = cells
+            .into_iter()
+            .map(|(key, weather)| (key, weather.decompress()))
+            .collect();
+    }
+}
+
+struct CellSim {
+    target: Weather,
+    // The time at which a new target should be chosen
+    next_change: f64,
+}
+
+/// Drives the evolution of a `WeatherMap`. This only runs on the server.
+pub struct WeatherSim {
+    cells: HashMap<Vec2<i32>, CellSim>,
+    forced: Option<Weather>,
+}
+
+impl WeatherSim {
+    pub fn new() -> Self {
+        Self {
+            cells: HashMap::new(),
+            forced: None,
+        }
+    }
+
+    /// Force every cell towards the given weather (or return to the natural weather if `None`).
+    pub fn set_forced(&mut self, forced: Option<Weather>) {
+        self.forced = forced;
+    }
+
+    pub fn forced(&self) -> Option<Weather> {
+        self.forced
+    }
+
+    fn pick_target<R: Rng>(rng: &mut R, climate: Climate) -> Weather {
+        let rain = if rng.gen::<f32>() < climate.humidity * 0.5 {
+            rng.gen_range(0.3, 1.0)
+        } else {
+            0.0
+        };
+        let cloud = (rng.gen::<f32>() * climate.humidity * 1.5).max(rain).min(1.0);
+        let wind_angle = rng.gen_range(0.0, std::f32::consts::PI * 2.0);
+        let wind_speed = rng.gen::<f32>() * climate.windiness * MAX_WIND_SPEED;
+        Weather::new(cloud, rain, Vec2::new(wind_angle.cos(), wind_angle.sin()) * wind_speed)
+    }
+
+    /// Simulate the weather of the cells covering the given chunks by `dt` seconds, `time` being
+    /// the current in-game time. Cells that no longer cover any of the chunks are removed.
+    pub fn tick<F: Fn(Vec2<i32>) -> Climate>(
+        &mut self,
+        time: f64,
+        dt: f64,
+        chunk_keys: impl Iterator<Item = Vec3<i32>>,
+        climate: F,
+        map: &mut WeatherMap,
+    ) {
+        let mut rng = rand::thread_rng();
+        let active = chunk_keys.map(WeatherMap::cell_key).collect::<HashSet<_>>();
+
+        self.cells.retain(|key, _| active.contains(key));
+        map.cells.retain(|key, _| active.contains(key));
+
+        let t = (1.0 - (-dt / TRANSITION_TIME).exp()) as f32;
+        for key in active {
+            let sim = self.cells.entry(key).or_insert_with(|| CellSim {
+                target: Self::pick_target(&mut rng, climate(key)),
+                next_change: time,
+            });
+
+            if time >= sim.next_change {
+                sim.target = Self::pick_target(&mut rng, climate(key));
+                sim.next_change = time + rng.gen_range(MIN_TARGET_DURATION, MAX_TARGET_DURATION);
+            }
+
+            let target = self.forced.unwrap_or(sim.target);
+            // New cells start out at their target weather rather than slowly fading in
+            let weather = map.cells.entry(key).or_insert(target);
+            *weather = weather.lerp(target, t);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compression_is_close_to_original() {
+        let weather = Weather::new(0.3, 0.75, Vec2::new(-12.0, 29.0));
+        let decompressed = weather.compress().decompress();
+
+        assert!((decompressed.cloud - weather.cloud).abs() < 0.01);
+        assert!((decompressed.rain - weather.rain).abs() < 0.01);
+        assert!(decompressed.wind.distance(weather.wind) < MAX_WIND_SPEED / 100.0);
+    }
+
+    #[test]
+    fn forced_weather_is_approached_and_inactive_cells_removed() {
+        let mut sim = WeatherSim::new();
+        let mut map = WeatherMap::new();
+        let storm = Weather::new(1.0, 1.0, Vec2::new(20.0, 0.0));
+        sim.set_forced(Some(storm));
+
+        let chunks = || vec![Vec3::new(0, 0, 0), Vec3::new(WEATHER_CELL_SIZE, 0, 0)].into_iter();
+        for i in 0..100 {
+            sim.tick(i as f64 * 10.0, 10.0, chunks(), |_| Climate::default(), &mut map);
+        }
+
+        assert!(map.get(Vec2::new(1, 0)).rain > 0.99);
+        assert_eq!(map.iter().count(), 2);
+
+        sim.tick(1000.0, 10.0, vec![Vec3::new(0, 0, 0)].into_iter(), |_| Climate::default(), &mut map);
+        assert_eq!(map.iter().count(), 1);
+        assert_eq!(map.get(Vec2::new(1, 0)), Weather::default());
+    }
+}
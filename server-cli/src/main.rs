@@ -1,9 +1,11 @@
-use std::time::Duration;
-use log::info;
+use std::time::{Duration, Instant};
+use log::{info, warn};
 use server::{Input, Event, Server};
 use common::clock::Clock;
 
 const TPS: u64 = 30;
+// Don't warn about slow ticks more often than this
+const OVERRUN_LOG_INTERVAL: Duration = Duration::from_secs(10);
 
 fn main() {
     // Init logging
@@ -18,6 +20,9 @@ fn main() {
     let mut server = Server::new()
         .expect("Failed to create server instance");
 
+    let mut logged_overruns = 0;
+    let mut last_overrun_log = Instant::now();
+
     loop {
         let events = server.tick(Input::default(), clock.get_last_delta())
             .expect("Failed to tick server");
@@ -35,5 +40,20 @@ fn main() {
 
         // Wait for the next tick
         clock.tick(Duration::from_millis(1000 / TPS));
+
+        // Let the operator know if the server can't keep up
+        if clock.get_overruns() > logged_overruns && last_overrun_log.elapsed() > OVERRUN_LOG_INTERVAL {
+            let stats = clock.get_stats();
+            warn!(
+                "{} ticks overran their time budget in the last {}s ({:.1} TPS, jitter {:.2}ms, max tick {:.2}ms)",
+                clock.get_overruns() - logged_overruns,
+                last_overrun_log.elapsed().as_secs(),
+                stats.tps,
+                stats.jitter.as_secs_f64() * 1000.0,
+                stats.max_delta.as_secs_f64() * 1000.0,
+            );
+            logged_overruns = clock.get_overruns();
+            last_overrun_log = Instant::now();
+        }
     }
 }
@@ -0,0 +1,187 @@
+//! # Terrain persistence
+//! Only chunks that differ from what the world generator would produce are stored. Chunks are
+//! grouped into region files of `REGION_SIZE`^3 chunks each to avoid creating huge numbers of tiny
+//! files on disk. Regions are kept in memory once they've been read, so loading a chunk only
+//! touches the disk the first time its region is used.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use vek::*;
+use common::terrain::TerrainChunk;
+use crate::persistence::Error;
+
+/// The number of chunks along each axis of a region.
+pub const REGION_SIZE: i32 = 16;
+
+type Region = HashMap<Vec3<i32>, TerrainChunk>;
+
+pub struct ChunkStore {
+    dir: PathBuf,
+    // Regions that have been used so far, or `None` if they haven't been read yet. Each region has
+    // its own lock, so worker threads only wait for each other when they use the same region.
+    regions: Mutex<HashMap<Vec3<i32>, Arc<Mutex<Option<Region>>>>>,
+}
+
+impl ChunkStore {
+    /// Create a new `ChunkStore` storing region files in `dir`, creating the directory if needed.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Result<Self, Error> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            regions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Get the key of the region that contains the chunk with the given key.
+    pub fn region_key(chunk_key: Vec3<i32>) -> Vec3<i32> {
+        chunk_key.map(|e| e.div_euclid(REGION_SIZE))
+    }
+
+    fn region_path(&self, region_key: Vec3<i32>) -> PathBuf {
+        self.dir.join(format!("r.{}.{}.{}.bin", region_key.x, region_key.y, region_key.z))
+    }
+
+    fn read_region(&self, region_key: Vec3<i32>) -> Result<Region, Error> {
+        match fs::read(self.region_path(region_key)) {
+            Ok(bytes) => Ok(bincode::deserialize(&bytes)?),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(Region::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn write_region(&self, region_key: Vec3<i32>, region: &Region) -> Result<(), Error> {
+        let path = self.region_path(region_key);
+        let tmp_path = path.with_extension("tmp");
+
+        // Write to a temporary file first so that a crash mid-write can't corrupt existing data
+        fs::write(&tmp_path, bincode::serialize(region)?)?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Run `f` on the region with the given key, reading it from disk first if it hasn't been used
+    /// yet. The region is locked for the duration of the call.
+    fn with_region<T>(
+        &self,
+        region_key: Vec3<i32>,
+        f: impl FnOnce(&mut Region) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let region = self
+            .regions
+            .lock()
+            .unwrap()
+            .entry(region_key)
+            .or_insert_with(|| Arc::new(Mutex::new(None)))
+            .clone();
+
+        let mut region = region.lock().unwrap();
+        if region.is_none() {
+            *region = Some(self.read_region(region_key)?);
+        }
+        f(region.as_mut().expect("The region was just read"))
+    }
+
+    /// Load a stored chunk, if one has been saved for this key.
+    ///
+    /// Note that this may perform blocking I/O and should be called from a worker thread.
+    pub fn load_chunk(&self, key: Vec3<i32>) -> Result<Option<TerrainChunk>, Error> {
+        self.with_region(Self::region_key(key), |region| Ok(region.get(&key).cloned()))
+    }
+
+    /// Store the provided chunks, replacing any previously stored versions of them.
+    ///
+    /// Note that this performs blocking I/O and should be called from a worker thread.
+    pub fn save_chunks(&self, chunks: Vec<(Vec3<i32>, TerrainChunk)>) -> Result<(), Error> {
+        // Group chunks by region so that each region file is only rewritten once
+        let mut regions: HashMap<Vec3<i32>, Vec<(Vec3<i32>, TerrainChunk)>> = HashMap::new();
+        for (key, chunk) in chunks {
+            regions
+                .entry(Self::region_key(key))
+                .or_insert_with(Vec::new)
+                .push((key, chunk));
+        }
+
+        for (region_key, chunks) in regions {
+            self.with_region(region_key, |region| {
+                region.extend(chunks);
+                self.write_region(region_key, region)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{
+        terrain::{Block, TerrainChunkMeta},
+        vol::ReadVol,
+    };
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("veloren-chunk-store-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn chunk(block: Block) -> TerrainChunk {
+        TerrainChunk::filled(block, TerrainChunkMeta::void())
+    }
+
+    // The colour of the stored chunk's blocks, if it has been stored
+    fn stored_color(store: &ChunkStore, key: Vec3<i32>) -> Option<Rgb<u8>> {
+        store
+            .load_chunk(key)
+            .unwrap()
+            .and_then(|chunk| chunk.get(Vec3::zero()).unwrap().get_color())
+    }
+
+    #[test]
+    fn chunks_round_trip() {
+        let dir = temp_dir("round-trip");
+        let stone = Block::new(1, Rgb::new(200, 220, 255));
+        let sand = Block::new(3, Rgb::new(180, 150, 50));
+        // Negative keys are in different regions to their positive counterparts
+        let (a, b) = (Vec3::new(1, 2, 0), Vec3::new(-1, -2, -1));
+        assert_ne!(ChunkStore::region_key(a), ChunkStore::region_key(b));
+
+        let store = ChunkStore::new(dir.clone()).unwrap();
+        assert_eq!(stored_color(&store, a), None);
+        store.save_chunks(vec![(a, chunk(stone)), (b, chunk(sand))]).unwrap();
+        assert_eq!(stored_color(&store, a), stone.get_color());
+        assert_eq!(stored_color(&store, b), sand.get_color());
+
+        // A fresh store has to read the chunks back from disk
+        let store = ChunkStore::new(dir.clone()).unwrap();
+        assert_eq!(stored_color(&store, a), stone.get_color());
+        assert_eq!(stored_color(&store, b), sand.get_color());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn saves_within_a_region_overwrite_only_their_chunks() {
+        let dir = temp_dir("overwrite");
+        let stone = Block::new(1, Rgb::new(200, 220, 255));
+        let sand = Block::new(3, Rgb::new(180, 150, 50));
+        let (a, b) = (Vec3::new(-3, 0, 1), Vec3::new(-4, 5, 2));
+        assert_eq!(ChunkStore::region_key(a), ChunkStore::region_key(b));
+
+        let store = ChunkStore::new(dir.clone()).unwrap();
+        store.save_chunks(vec![(a, chunk(stone)), (b, chunk(stone))]).unwrap();
+        store.save_chunks(vec![(a, chunk(sand))]).unwrap();
+
+        let store = ChunkStore::new(dir.clone()).unwrap();
+        assert_eq!(stored_color(&store, a), sand.get_color());
+        assert_eq!(stored_color(&store, b), stone.get_color());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
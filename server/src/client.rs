@@ -1,5 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use specs::Entity as EcsEntity;
+use vek::*;
 use common::{
     comp,
     msg::{ServerMsg, ClientMsg},
@@ -17,6 +18,8 @@ pub struct Client {
     pub state: ClientState,
     pub postbox: PostBox<ServerMsg, ClientMsg>,
     pub last_ping: f64,
+    /// Chunks that this client has requested and should be kept up to date about
+    pub chunks: HashSet<Vec3<i32>>,
 }
 
 impl Client {
@@ -58,6 +61,14 @@ impl Clients {
         }
     }
 
+    pub fn notify_connected_with_chunk(&mut self, key: Vec3<i32>, msg: ServerMsg) {
+        for client in self.clients.values_mut() {
+            if client.state == ClientState::Connected && client.chunks.contains(&key) {
+                client.notify(msg.clone());
+            }
+        }
+    }
+
     pub fn notify_connected_except(&mut self, except_entity: EcsEntity, msg: ServerMsg) {
         for (entity, client) in self.clients.iter_mut() {
             if client.state == ClientState::Connected && *entity != except_entity {
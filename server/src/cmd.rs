@@ -2,14 +2,18 @@
 //! To implement a new command, add an instance of `ChatCommand` to `CHAT_COMMANDS`
 //! and provide a handler function.
 
-use crate::Server;
-use common::{comp, msg::ServerMsg};
+use crate::{profile::MAX_TRACE_EVENTS, Server};
+use common::{comp, msg::ServerMsg, weather::Weather};
 use specs::{join::Join, Entity as EcsEntity};
 use vek::*;
 
 
 use lazy_static::lazy_static;
 use scan_fmt::scan_fmt;
+
+/// The file that `/profile trace` writes to, in the Chrome trace event format
+const PROFILE_TRACE_PATH: &str = "profile_trace.json";
+
 /// Struct representing a command that a user can run from server chat
 pub struct ChatCommand {
     /// The keyword used to invoke the command, omitting the leading '/'
@@ -76,6 +80,24 @@ lazy_static! {
             "/tp <alias>: Teleport to another player",
             handle_tp
         ),
+        ChatCommand::new(
+            "profile",
+            "{}",
+            "/profile [trace] : Show how long each part of a server tick takes, or toggle writing a trace file (admin only)",
+            handle_profile
+        ),
+        ChatCommand::new(
+            "daylength",
+            "{f}",
+            "/daylength <seconds> : Set how many real seconds an in-game day lasts (admin only)",
+            handle_day_length
+        ),
+        ChatCommand::new(
+            "weather",
+            "{}",
+            "/weather <clear|cloudy|rain|storm|auto> : Force the weather everywhere, or let it change naturally again (admin only)",
+            handle_weather
+        ),
         ChatCommand::new("help", "", "/help: Display this message", handle_help)
     ];
 }
@@ -169,6 +191,101 @@ fn handle_tp(server: &mut Server, entity: EcsEntity, args: String, action: &Chat
     }
 }
 
+fn handle_profile(server: &mut Server, entity: EcsEntity, args: String, action: &ChatCommand) {
+    match scan_fmt!(&args, action.arg_fmt, String).as_ref().map(|s| s.as_str()) {
+        None => {
+            let mut lines = vec![String::from("Tick timings (p50 / p95 / max, ms):")];
+            for (name, stats) in server.profiler().summary() {
+                lines.push(format!(
+                    "{}: {:.2} / {:.2} / {:.2}",
+                    name,
+                    stats.p50.as_secs_f64() * 1000.0,
+                    stats.p95.as_secs_f64() * 1000.0,
+                    stats.max.as_secs_f64() * 1000.0,
+                ));
+            }
+            server
+                .clients
+                .notify(entity, ServerMsg::Chat(lines.join("\n")));
+        }
+        Some("trace") => {
+            if !require_admin(server, entity) {
+                return;
+            }
+            let msg = if server.profiler().is_tracing() {
+                match server.profiler_mut().stop_trace() {
+                    Ok(()) => format!("Trace written to '{}'", PROFILE_TRACE_PATH),
+                    Err(err) => format!("Failed to write trace: {}", err),
+                }
+            } else {
+                match server.profiler_mut().start_trace(PROFILE_TRACE_PATH) {
+                    Ok(()) => format!(
+                        "Writing trace to '{}', run '/profile trace' again to stop (it stops by itself after {} events)",
+                        PROFILE_TRACE_PATH,
+                        MAX_TRACE_EVENTS,
+                    ),
+                    Err(err) => format!("Failed to start trace: {}", err),
+                }
+            };
+            server.clients.notify(entity, ServerMsg::Chat(msg));
+        }
+        Some(_) => server
+            .clients
+            .notify(entity, ServerMsg::Chat(String::from(action.help_string))),
+    }
+}
+
+/// Check that a player may use an admin command, telling them if they can't.
+fn require_admin(server: &mut Server, entity: EcsEntity) -> bool {
+    let is_admin = server.is_admin(entity);
+    if !is_admin {
+        server.clients.notify(
+            entity,
+            ServerMsg::Chat(String::from("You don't have permission to use this command")),
+        );
+    }
+    is_admin
+}
+
+fn handle_day_length(server: &mut Server, entity: EcsEntity, args: String, action: &ChatCommand) {
+    if !require_admin(server, entity) {
+        return;
+    }
+    let msg = match scan_fmt!(&args, action.arg_fmt, f64) {
+        Some(day_length) if server.set_day_length(day_length) =>
+            format!("A day now lasts {} seconds", day_length),
+        Some(_) => String::from("The length of a day must be a positive number of seconds"),
+        None => String::from(action.help_string),
+    };
+    server.clients.notify(entity, ServerMsg::Chat(msg));
+}
+
+fn handle_weather(server: &mut Server, entity: EcsEntity, args: String, action: &ChatCommand) {
+    if !require_admin(server, entity) {
+        return;
+    }
+    let opt_weather = scan_fmt!(&args, action.arg_fmt, String);
+    let forced = match opt_weather.as_ref().map(|s| s.as_str()) {
+        Some("clear") => Some(Weather::new(0.0, 0.0, Vec2::new(2.0, 0.0))),
+        Some("cloudy") => Some(Weather::new(0.8, 0.0, Vec2::new(6.0, 0.0))),
+        Some("rain") => Some(Weather::new(1.0, 0.6, Vec2::new(8.0, 0.0))),
+        Some("storm") => Some(Weather::new(1.0, 1.0, Vec2::new(25.0, 0.0))),
+        Some("auto") => None,
+        _ => {
+            server
+                .clients
+                .notify(entity, ServerMsg::Chat(String::from(action.help_string)));
+            return;
+        }
+    };
+
+    server.set_forced_weather(forced);
+    server.clients.notify(
+        entity,
+        ServerMsg::Chat(format!("Weather set to '{}'", opt_weather.unwrap_or_default())),
+    );
+}
+
 fn handle_help(server: &mut Server, entity: EcsEntity, _args: String, _action: &ChatCommand) {
     for cmd in CHAT_COMMANDS.iter() {
         server
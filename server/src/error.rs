@@ -1,8 +1,13 @@
-use common::net::PostError;
+use common::{item, net::PostError, spawn};
+use crate::{persistence, settings};
 
 #[derive(Debug)]
 pub enum Error {
     Network(PostError),
+    Persistence(persistence::Error),
+    Item(item::Error),
+    Spawn(spawn::Error),
+    Settings(settings::Error),
     Other(String),
 }
 
@@ -11,3 +16,27 @@ impl From<PostError> for Error {
         Error::Network(err)
     }
 }
+
+impl From<persistence::Error> for Error {
+    fn from(err: persistence::Error) -> Self {
+        Error::Persistence(err)
+    }
+}
+
+impl From<item::Error> for Error {
+    fn from(err: item::Error) -> Self {
+        Error::Item(err)
+    }
+}
+
+impl From<spawn::Error> for Error {
+    fn from(err: spawn::Error) -> Self {
+        Error::Spawn(err)
+    }
+}
+
+impl From<settings::Error> for Error {
+    fn from(err: settings::Error) -> Self {
+        Error::Settings(err)
+    }
+}
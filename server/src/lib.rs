@@ -1,32 +1,77 @@
-#![feature(drain_filter)]
+#![feature(drain_filter, euclidean_division)]
 
+pub mod chunk_store;
 pub mod client;
 pub mod error;
 pub mod input;
+pub mod mount;
 pub mod cmd;
+pub mod persistence;
+pub mod profile;
+pub mod settings;
 
 // Reexports
 pub use crate::{error::Error, input::Input};
 
-use crate::{client::{Client, ClientState, Clients}, cmd::CHAT_COMMANDS};
+use crate::{
+    chunk_store::ChunkStore,
+    client::{Client, ClientState, Clients},
+    cmd::CHAT_COMMANDS,
+    persistence::{Backend as PersistenceBackend, CharacterData, CharacterName, FileBackend, PlayerData},
+    profile::Profiler,
+    settings::{Admin, BuildPermission, ServerSettings},
+};
 use common::{
-    comp,
-    msg::{ClientMsg, ServerMsg},
+    comp::{self, mount::MOUNT_REACH},
+    item::ItemRegistry,
+    msg::{CharacterInfo, ClientMsg, ServerMsg},
     net::PostOffice,
-    state::{State, Uid},
-    terrain::TerrainChunk,
+    path,
+    spawn::{self, SpawnRules, Spawned},
+    state::{State, Uid, FixedStep},
+    sys,
+    terrain::{Block, TerrainChunk, TerrainMap},
+    vol::{ReadVol, Vox},
     comp::character::Animation,
+    weather::WeatherSim,
 };
 use specs::{
     join::Join, saveload::MarkedBuilder, world::EntityBuilder as EcsEntityBuilder, Builder,
     Entity as EcsEntity,
 };
-use std::{collections::HashSet, net::SocketAddr, sync::mpsc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    time::{Duration, Instant},
+};
+use rand::Rng;
 use threadpool::ThreadPool;
 use vek::*;
 use world::World;
+use log::warn;
 
 const CLIENT_TIMEOUT: f64 = 20.0; // Seconds
+const BLOCK_REACH: f32 = 8.0; // Blocks
+const PICKUP_REACH: f32 = 3.0; // Blocks
+// Above the generated terrain, so that new characters fall onto it once it has loaded
+const SPAWN_POS: Vec3<f32> = Vec3 { x: 0.0, y: 0.0, z: 40.0 };
+const PLAYER_SAVE_INTERVAL: f64 = 60.0; // Seconds
+const PLAYER_SAVE_DIR: &str = "saves/players";
+const CHUNK_SAVE_INTERVAL: f64 = 10.0; // Seconds
+const CHUNK_SAVE_DIR: &str = "saves/chunks";
+const TIME_SYNC_INTERVAL: f64 = 10.0; // Seconds
+const WEATHER_SYNC_INTERVAL: f64 = 5.0; // Seconds
+const CHARACTER_HEALTH: u32 = 100;
+const RESPAWN_DELAY: f64 = 5.0; // Seconds
+const SPAWN_INTERVAL: f64 = 5.0; // Seconds
+const SPAWN_RADIUS: i32 = 2; // Chunks around each player
+const MIN_SPAWN_DISTANCE: f32 = 24.0; // Blocks from the nearest player
+const GROUP_SPREAD: i32 = 3; // Blocks between members of a spawned group
+const DESPAWN_DISTANCE: f32 = 128.0; // Blocks from the nearest player
 
 pub enum Event {
     ClientConnected { entity: EcsEntity },
@@ -35,6 +80,8 @@ pub enum Event {
 }
 
 pub struct Server {
+    settings: ServerSettings,
+
     state: State,
     world: World,
 
@@ -45,18 +92,86 @@ pub struct Server {
     chunk_tx: mpsc::Sender<(Vec3<i32>, TerrainChunk)>,
     chunk_rx: mpsc::Receiver<(Vec3<i32>, TerrainChunk)>,
     pending_chunks: HashSet<Vec3<i32>>,
+    // Incremented every time a chunk is modified so that clients can detect missed updates. These
+    // aren't saved: clients lose their chunks when the server goes down, so after a restart they
+    // only ever hold chunks sent (with their versions) by the new server.
+    chunk_versions: HashMap<Vec3<i32>, u64>,
+
+    chunk_store: Arc<ChunkStore>,
+    // Chunks that have been modified since they were last saved
+    dirty_chunks: HashSet<Vec3<i32>>,
+    // Chunks that a background save failed to write, to be retried with the next save
+    unsaved_chunk_tx: mpsc::Sender<Vec<Vec3<i32>>>,
+    unsaved_chunk_rx: mpsc::Receiver<Vec<Vec3<i32>>>,
+    chunk_save_in_progress: Arc<AtomicBool>,
+    last_chunk_save: f64,
+
+    persistence: Box<dyn PersistenceBackend>,
+    last_player_save: f64,
+
+    profiler: Profiler,
+
+    last_time_sync: f64,
+
+    weather_sim: WeatherSim,
+    last_weather_sync: f64,
+
+    items: ItemRegistry,
+
+    spawn_rules: SpawnRules,
+    last_spawn: f64,
+}
+
+/// A request from a client to manage its account's characters.
+enum CharacterAction {
+    List,
+    Create { name: String, character: comp::Character },
+    Delete { name: String },
+    Select { name: String },
+}
+
+/// An inventory operation requested by a client.
+enum InventoryAction {
+    Move { from: usize, to: usize },
+    Split { slot: usize, amount: u32 },
+    Drop { slot: usize },
+    PickUp { item_entity: u64 },
+}
+
+/// A request from a client to start or stop riding a creature.
+enum MountAction {
+    Mount { mount: u64 },
+    Dismount,
 }
 
 impl Server {
     /// Create a new `Server`.
     #[allow(dead_code)]
     pub fn new() -> Result<Self, Error> {
-        let (chunk_tx, chunk_rx) = mpsc::channel();
+        Self::with_persistence(Box::new(FileBackend::new(PLAYER_SAVE_DIR)?))
+    }
 
-        let mut state = State::new();
+    /// Create a new `Server` that stores player data using the provided persistence backend.
+    #[allow(dead_code)]
+    pub fn with_persistence(persistence: Box<dyn PersistenceBackend>) -> Result<Self, Error> {
+        let (chunk_tx, chunk_rx) = mpsc::channel();
+        let (unsaved_chunk_tx, unsaved_chunk_rx) = mpsc::channel();
+        let settings = ServerSettings::load()?;
+
+        // Agents are controlled before control is applied, so they react within the same tick
+        let mut state = State::with_input_system(sys::agent::Sys::new(), sys::AGENT_SYS)
+            .with_fixed_step(FixedStep::default())
+            .with_system(sys::stats::Sys::default(), sys::STATS_SYS, &[])
+            .with_system(sys::combat::Sys, sys::COMBAT_SYS, &[sys::CONTROL_SYS]);
         state.ecs_mut().internal_mut().register::<comp::phys::ForceUpdate>();
+        state.ecs_mut().internal_mut().register::<CharacterName>();
+        state.ecs_mut().internal_mut().register::<Spawned>();
+        state.ecs_mut().internal_mut().register::<Admin>();
+        state.set_day_length(settings.day_length);
+
+        Ok(Self {
+            settings,
 
-        let mut this = Self {
             state,
             world: World::new(),
 
@@ -69,16 +184,59 @@ impl Server {
             chunk_tx,
             chunk_rx,
             pending_chunks: HashSet::new(),
-        };
+            chunk_versions: HashMap::new(),
 
-        for i in 0..4 {
-            this.create_character(comp::Character::test())
-                .with(comp::Agent::Wanderer(Vec2::zero()))
-                .with(comp::Control::default())
-                .build();
+            chunk_store: Arc::new(ChunkStore::new(CHUNK_SAVE_DIR)?),
+            dirty_chunks: HashSet::new(),
+            unsaved_chunk_tx,
+            unsaved_chunk_rx,
+            chunk_save_in_progress: Arc::new(AtomicBool::new(false)),
+            last_chunk_save: 0.0,
+
+            persistence,
+            last_player_save: 0.0,
+
+            profiler: Profiler::new(),
+
+            last_time_sync: 0.0,
+
+            weather_sim: WeatherSim::new(),
+            last_weather_sync: 0.0,
+
+            items: ItemRegistry::load()?,
+
+            spawn_rules: SpawnRules::load()?,
+            last_spawn: 0.0,
+        })
+    }
+
+    /// Get a reference to the server's tick profiler.
+    #[allow(dead_code)]
+    pub fn profiler(&self) -> &Profiler {
+        &self.profiler
+    }
+
+    /// Get a mutable reference to the server's tick profiler.
+    #[allow(dead_code)]
+    pub fn profiler_mut(&mut self) -> &mut Profiler {
+        &mut self.profiler
+    }
+
+    /// Set how many real seconds an in-game day lasts, notifying clients of the change. Returns
+    /// false if the length isn't a positive number of seconds.
+    pub fn set_day_length(&mut self, day_length: f64) -> bool {
+        let changed = self.state.set_day_length(day_length);
+        if changed {
+            self.sync_time_of_day();
         }
+        changed
+    }
 
-        Ok(this)
+    /// Set the current in-game time of day, notifying clients of the change.
+    #[allow(dead_code)]
+    pub fn set_time_of_day(&mut self, time_of_day: f64) {
+        self.state.set_time_of_day(time_of_day);
+        self.sync_time_of_day();
     }
 
     /// Get a reference to the server's game state.
@@ -103,16 +261,35 @@ impl Server {
         &mut self.world
     }
 
-    /// Build a non-player character
+    /// Get a reference to the definitions of all items.
+    #[allow(dead_code)]
+    pub fn items(&self) -> &ItemRegistry {
+        &self.items
+    }
+
+    /// Build a non-player humanoid at the given position
+    #[allow(dead_code)]
+    pub fn create_character(&mut self, pos: comp::phys::Pos, character: comp::Character) -> EcsEntityBuilder {
+        self.create_npc(pos, comp::Body::Humanoid(character))
+    }
+
+    /// Build a non-player creature with the given body at the given position
     #[allow(dead_code)]
-    pub fn create_character(&mut self, character: comp::Character) -> EcsEntityBuilder {
+    pub fn create_npc(&mut self, pos: comp::phys::Pos, body: comp::Body) -> EcsEntityBuilder {
         self.state
             .ecs_mut()
             .create_entity_synced()
-            .with(comp::phys::Pos(Vec3::zero()))
+            .with(pos)
             .with(comp::phys::Vel(Vec3::zero()))
             .with(comp::phys::Dir(Vec3::unit_y()))
-            .with(character)
+            .with(comp::MovementStats::default())
+            .with(comp::Stats::new(CHARACTER_HEALTH))
+            .with(comp::Inventory::default())
+            .with(comp::AnimationHistory {
+                last: None,
+                current: Animation::Idle,
+            })
+            .with(body)
     }
 
     /// Execute a single server tick, handle input and update the game state by the given duration
@@ -132,6 +309,8 @@ impl Server {
         // 6) Send relevant state updates to all clients
         // 7) Finish the tick, passing control of the main thread back to the frontend
 
+        self.profiler.begin_tick();
+
         // Build up a list of events for this frame, to be passed to the frontend
         let mut frontend_events = Vec::new();
 
@@ -141,45 +320,91 @@ impl Server {
         }
 
         // Handle new client connections (step 2)
+        let start = Instant::now();
         frontend_events.append(&mut self.handle_new_connections()?);
+        self.profiler.record("handle_connections", start);
 
         // Handle new messages from clients
+        let start = Instant::now();
         frontend_events.append(&mut self.handle_new_messages()?);
+        self.profiler.record("handle_messages", start);
 
         // Tick the client's LocalState (step 3)
-        self.state.tick(dt);
+        let start = Instant::now();
+        let steps = self.state.tick(dt);
+        self.profiler.record("state_tick", start);
+        if steps > 0 {
+            self.profiler.record_systems(start, self.state.sys_timings());
+        }
+
+        // Apply damage dealt this tick and handle anything that died as a result
+        let start = Instant::now();
+        self.handle_damage();
+        self.handle_deaths();
+        self.profiler.record("damage", start);
+
+        // Keep the regions around players populated
+        if self.state.get_time() - self.last_spawn > SPAWN_INTERVAL {
+            let start = Instant::now();
+            self.update_population();
+            self.profiler.record("spawn", start);
+        }
+
+        // Evolve the weather over the loaded parts of the world
+        let start = Instant::now();
+        let chunk_keys = self.state.terrain().iter().map(|(key, _)| key).collect::<Vec<_>>();
+        let time = self.state.get_time();
+        self.weather_sim.tick(
+            time,
+            dt.as_secs_f64(),
+            chunk_keys.into_iter(),
+            World::climate,
+            &mut self.state.weather_mut(),
+        );
+        self.profiler.record("weather", start);
 
         // Fetch any generated `TerrainChunk`s and insert them into the terrain
-        // Also, send the chunk data to anybody that is close by
+        // Also, send the chunk data to anybody that requested it
+        let start = Instant::now();
         for (key, chunk) in self.chunk_rx.try_iter() {
-            // Send the chunk to all nearby players
-            for (entity, player, pos) in (
-                &self.state.ecs().internal().entities(),
-                &self.state.ecs().internal().read_storage::<comp::Player>(),
-                &self
-                    .state
-                    .ecs()
-                    .internal()
-                    .read_storage::<comp::phys::Pos>(),
-            )
-                .join()
-            {
-                // TODO: Distance check
-                // if self.state.terrain().key_pos(key)
-
-                /*
-                self.clients.notify(entity, ServerMsg::TerrainChunkUpdate {
-                    key,
-                    chunk: Box::new(chunk.clone()),
-                });
-                */
-            }
+            self.clients.notify_connected_with_chunk(key, ServerMsg::TerrainChunkUpdate {
+                key,
+                version: self.chunk_versions.get(&key).cloned().unwrap_or(0),
+                chunk: Box::new(chunk.clone()),
+            });
 
             self.state.insert_chunk(key, chunk);
+            self.pending_chunks.remove(&key);
+        }
+        self.profiler.record("chunk_receive", start);
+
+        // Any chunk that changed this tick no longer matches the generated terrain, so it must
+        // be saved
+        self.dirty_chunks
+            .extend(self.state.changes().changed_chunks.iter().cloned());
+        self.dirty_chunks.extend(self.unsaved_chunk_rx.try_iter().flatten());
+
+        if self.state.get_time() - self.last_chunk_save > CHUNK_SAVE_INTERVAL {
+            let start = Instant::now();
+            self.save_dirty_chunks();
+            self.last_chunk_save = self.state.get_time();
+            self.profiler.record("chunk_save", start);
         }
 
         // Synchronise clients with the new state of the world
+        let start = Instant::now();
         self.sync_clients();
+        self.profiler.record("sync_clients", start);
+
+        // Periodically save player data so that it isn't lost if the server goes down
+        if self.state.get_time() - self.last_player_save > PLAYER_SAVE_INTERVAL {
+            let start = Instant::now();
+            self.save_all_players();
+            self.last_player_save = self.state.get_time();
+            self.profiler.record("player_save", start);
+        }
+
+        self.profiler.end_tick();
 
         // Finish the tick, pass control back to the frontend (step 6)
         Ok(frontend_events)
@@ -205,6 +430,7 @@ impl Server {
                     state: ClientState::Connecting,
                     postbox,
                     last_ping: self.state.get_time(),
+                    chunks: HashSet::new(),
                 },
             );
 
@@ -218,10 +444,16 @@ impl Server {
     fn handle_new_messages(&mut self) -> Result<Vec<Event>, Error> {
         let mut frontend_events = Vec::new();
 
+        let settings = &self.settings;
         let state = &mut self.state;
+        let chunk_versions = &self.chunk_versions;
         let mut new_chat_msgs = Vec::new();
         let mut disconnected_clients = Vec::new();
         let mut requested_chunks = Vec::new();
+        let mut block_modifications = Vec::new();
+        let mut inventory_actions = Vec::new();
+        let mut character_actions = Vec::new();
+        let mut mount_actions = Vec::new();
 
         self.clients.remove_if(|entity, client| {
             let mut disconnect = false;
@@ -235,13 +467,19 @@ impl Server {
                 for msg in new_msgs {
                     match client.state {
                         ClientState::Connecting => match msg {
-                            ClientMsg::Connect { player, character } => {
-                                Self::initialize_client(state, entity, client, player, character);
-                            }
+                            ClientMsg::Connect { player } => Self::initialize_client(settings, state, entity, client, player),
                             _ => disconnect = true,
                         },
                         ClientState::Connected => match msg {
                             ClientMsg::Connect { .. } => disconnect = true, // Not allowed when already connected
+                            ClientMsg::RequestCharacterList =>
+                                character_actions.push((entity, CharacterAction::List)),
+                            ClientMsg::CreateCharacter { name, character } =>
+                                character_actions.push((entity, CharacterAction::Create { name, character })),
+                            ClientMsg::DeleteCharacter { name } =>
+                                character_actions.push((entity, CharacterAction::Delete { name })),
+                            ClientMsg::SelectCharacter { name } =>
+                                character_actions.push((entity, CharacterAction::Select { name })),
                             ClientMsg::Disconnect => disconnect = true,
                             ClientMsg::Ping => client.postbox.send_message(ServerMsg::Pong),
                             ClientMsg::Pong => {}
@@ -252,15 +490,47 @@ impl Server {
                                 state.write_component(entity, vel);
                                 state.write_component(entity, dir);
                             }
+                            ClientMsg::Attack => {
+                                let alive = state
+                                    .read_storage::<comp::Stats>()
+                                    .get(entity)
+                                    .map(|stats| !stats.is_dead())
+                                    .unwrap_or(false);
+                                // Attacks that are still in progress act as a cooldown
+                                if alive && state.read_storage::<comp::Attacking>().get(entity).is_none() {
+                                    state.write_component(entity, comp::Attacking::default());
+                                }
+                            }
+                            ClientMsg::MoveInventorySlot { from, to } =>
+                                inventory_actions.push((entity, InventoryAction::Move { from, to })),
+                            ClientMsg::SplitInventorySlot { slot, amount } =>
+                                inventory_actions.push((entity, InventoryAction::Split { slot, amount })),
+                            ClientMsg::DropInventorySlot { slot } =>
+                                inventory_actions.push((entity, InventoryAction::Drop { slot })),
+                            ClientMsg::PickUp { entity: item_entity } =>
+                                inventory_actions.push((entity, InventoryAction::PickUp { item_entity })),
+                            ClientMsg::Mount { entity: mount } =>
+                                mount_actions.push((entity, MountAction::Mount { mount })),
+                            ClientMsg::Dismount => mount_actions.push((entity, MountAction::Dismount)),
+                            ClientMsg::RiderControl(control) => {
+                                // Only riders steer anything with their controls on the server
+                                if state.read_storage::<comp::Rider>().get(entity).is_some() {
+                                    state.write_component(entity, control);
+                                }
+                            }
                             ClientMsg::TerrainChunkRequest { key } => {
+                                client.chunks.insert(key);
                                 match state.terrain().get_key(key) {
-                                    Some(chunk) => {} /*client.postbox.send_message(ServerMsg::TerrainChunkUpdate {
-                                    key,
-                                    chunk: Box::new(chunk.clone()),
-                                    }),*/
+                                    Some(chunk) => client.postbox.send_message(ServerMsg::TerrainChunkUpdate {
+                                        key,
+                                        version: chunk_versions.get(&key).cloned().unwrap_or(0),
+                                        chunk: Box::new(chunk.clone()),
+                                    }),
                                     None => requested_chunks.push(key),
                                 }
                             }
+                            ClientMsg::SetBlock { pos, block } => block_modifications.push((entity, pos, block)),
+                            ClientMsg::RemoveBlock { pos } => block_modifications.push((entity, pos, Block::empty())),
                         },
                     }
                 }
@@ -306,8 +576,42 @@ impl Server {
             }
         }
 
+        // Handle terrain modifications, batching the resulting updates by chunk
+        let mut modified_blocks: HashMap<Vec3<i32>, Vec<Vec3<i32>>> = HashMap::new();
+        for (entity, pos, block) in block_modifications {
+            if self.modify_block(entity, pos, block) {
+                modified_blocks
+                    .entry(self.state.terrain().pos_key(pos))
+                    .or_insert_with(Vec::new)
+                    .push(pos);
+            }
+        }
+        for (key, positions) in modified_blocks {
+            self.send_chunk_changes(key, positions);
+        }
+
+        // Handle character management
+        for (entity, action) in character_actions {
+            self.handle_character_action(entity, action);
+        }
+
+        // Handle inventory operations
+        for (entity, action) in inventory_actions {
+            self.handle_inventory_action(entity, action);
+        }
+
+        // Handle mounting and dismounting
+        for (entity, action) in mount_actions {
+            match action {
+                MountAction::Mount { mount } => self.mount(entity, mount),
+                MountAction::Dismount => self.dismount(entity),
+            }
+        }
+
         // Handle client disconnects
         for entity in disconnected_clients {
+            self.dismount(entity);
+            self.save_player(entity);
             self.state.ecs_mut().delete_entity_synced(entity);
 
             frontend_events.push(Event::ClientDisconnected { entity });
@@ -323,33 +627,18 @@ impl Server {
 
     /// Initialize a new client states with important information
     fn initialize_client(
+        settings: &ServerSettings,
         state: &mut State,
         entity: specs::Entity,
         client: &mut Client,
         player: comp::Player,
-        character: Option<comp::Character>,
     ) {
-        // Save player metadata (for example the username)
-        state.write_component(entity, player);
-
-        // Give the player it's character if he wants one
-        // (Chat only clients don't need one for example)
-        if let Some(character) = character {
-            state.write_component(entity, character);
-
-            // Every character has to have these components
-            state.write_component(entity, comp::phys::Pos(Vec3::zero()));
-            state.write_component(entity, comp::phys::Vel(Vec3::zero()));
-            state.write_component(entity, comp::phys::Dir(Vec3::unit_y()));
-            // Make sure everything is accepted
-            state.write_component(entity, comp::phys::ForceUpdate);
-
-            // Set initial animation
-            state.write_component(entity, comp::AnimationHistory {
-                last: None,
-                current: Animation::Idle
-            });
+        // Save player metadata (for example the username). The player picks a character later,
+        // if they want one (chat only clients don't need one for example).
+        if settings.is_admin(&player.alias) {
+            state.write_component(entity, Admin);
         }
+        state.write_component(entity, player);
 
         client.state = ClientState::Connected;
 
@@ -362,7 +651,10 @@ impl Server {
                 .uid_from_entity(entity)
                 .unwrap()
                 .into(),
+            time_of_day: state.get_time_of_day(),
+            day_length: state.get_day_length(),
         });
+        client.notify(ServerMsg::Weather(state.weather().compress()));
 
         // Sync logical information other players have authority over, not the server
         for (other_entity, &uid, &animation_history) in (
@@ -378,6 +670,324 @@ impl Server {
         }
     }
 
+    /// Handle a request to list, create, delete or select one of a player's characters. The
+    /// player is sent the updated character list, or an error if the request was refused.
+    fn handle_character_action(&mut self, entity: EcsEntity, action: CharacterAction) {
+        let account = match self.state.read_component_cloned::<comp::Player>(entity) {
+            Some(player) => player.alias,
+            None => return,
+        };
+
+        // Characters can only be managed before entering the world
+        if self.state.read_storage::<comp::Body>().get(entity).is_some() {
+            self.clients.notify(entity, ServerMsg::CharacterActionError(String::from("Already in the world")));
+            return;
+        }
+
+        // Refuse to touch data that couldn't be loaded rather than overwrite it
+        let mut data = match Self::load_player(&*self.persistence, &account) {
+            Ok(data) => data,
+            Err(err) => {
+                self.clients.notify(entity, ServerMsg::CharacterActionError(String::from(err)));
+                return;
+            },
+        };
+        let result = match action {
+            CharacterAction::List => Ok(()),
+            CharacterAction::Create { name, character } => {
+                let name = name.trim().to_string();
+                match data.can_create_character(&name, &character) {
+                    Err(err) => Err(err),
+                    Ok(()) => {
+                        data.set_character(CharacterData {
+                            name,
+                            character,
+                            pos: comp::phys::Pos(SPAWN_POS),
+                            dir: comp::phys::Dir(Vec3::unit_y()),
+                            stats: comp::Stats::new(CHARACTER_HEALTH),
+                            inventory: comp::Inventory::default(),
+                        });
+                        self.store_player(&account, &data)
+                    },
+                }
+            },
+            CharacterAction::Delete { name } => if data.remove_character(&name) {
+                self.store_player(&account, &data)
+            } else {
+                Err("No such character")
+            },
+            CharacterAction::Select { name } => match data.character(&name) {
+                Some(character) => {
+                    self.initialize_character(entity, character.clone());
+                    self.clients.notify(entity, ServerMsg::CharacterSelected);
+                    return;
+                },
+                None => Err("No such character"),
+            },
+        };
+
+        self.clients.notify(entity, match result {
+            Ok(()) => ServerMsg::CharacterList(data.characters
+                .iter()
+                .map(|data| CharacterInfo {
+                    name: data.name.clone(),
+                    character: data.character,
+                })
+                .collect()),
+            Err(err) => ServerMsg::CharacterActionError(String::from(err)),
+        });
+    }
+
+    /// Put a player's character into the world, where they left it last time.
+    fn initialize_character(&mut self, entity: EcsEntity, data: CharacterData) {
+        // Characters saved while dead come back to life, like any other respawn
+        let stats = if data.stats.is_dead() {
+            comp::Stats::new(data.stats.max_health)
+        } else {
+            data.stats
+        };
+
+        let state = &mut self.state;
+        state.write_component(entity, comp::Body::Humanoid(data.character));
+        state.write_component(entity, CharacterName(data.name));
+
+        // Every character has to have these components
+        state.write_component(entity, data.pos);
+        state.write_component(entity, comp::phys::Vel(Vec3::zero()));
+        state.write_component(entity, data.dir);
+        state.write_component(entity, comp::MovementStats::default());
+        state.write_component(entity, stats);
+        state.write_component(entity, data.inventory.clone());
+        // Make sure everything is accepted
+        state.write_component(entity, comp::phys::ForceUpdate);
+
+        // Set initial animation
+        state.write_component(entity, comp::AnimationHistory {
+            last: None,
+            current: Animation::Idle
+        });
+
+        self.clients.notify(entity, ServerMsg::InventoryUpdate(data.inventory));
+    }
+
+    /// Send the server's time of day to all clients
+    fn sync_time_of_day(&mut self) {
+        self.clients.notify_connected(ServerMsg::TimeOfDay {
+            time_of_day: self.state.get_time_of_day(),
+            day_length: self.state.get_day_length(),
+        });
+        self.last_time_sync = self.state.get_time();
+    }
+
+    /// Damage an entity. The damage is applied at the end of the current (or next) tick.
+    #[allow(dead_code)]
+    pub fn damage(&mut self, target: EcsEntity, amount: u32, source: Option<EcsEntity>) {
+        self.state
+            .ecs_mut()
+            .internal_mut()
+            .write_resource::<comp::DamageQueue>()
+            .0
+            .push(comp::Damage { target, amount, source });
+    }
+
+    /// Apply all damage queued up by systems and commands, killing entities that run out of health
+    fn handle_damage(&mut self) {
+        let damages = std::mem::replace(
+            &mut self.state.ecs_mut().internal_mut().write_resource::<comp::DamageQueue>().0,
+            Vec::new(),
+        );
+        let time = self.state.get_time();
+
+        for comp::Damage { target, amount, source } in damages {
+            let killed = match self.state.ecs().internal().write_storage::<comp::Stats>().get_mut(target) {
+                // Dead entities can't be damaged any further
+                Some(stats) if !stats.is_dead() => stats.damage(amount),
+                _ => continue,
+            };
+            let source_uid = source
+                .and_then(|source| self.state.ecs().uid_from_entity(source))
+                .map(|uid| uid.into());
+
+            self.clients.notify(target, ServerMsg::Damage { amount, source: source_uid });
+
+            if killed {
+                self.dismount(target);
+                self.state.write_component(target, comp::Dead { time_of_death: time });
+                self.state.write_component(target, comp::AnimationHistory {
+                    last: None,
+                    current: Animation::Death,
+                });
+                self.clients.notify(target, ServerMsg::Death { killer: source_uid });
+            }
+        }
+    }
+
+    /// Respawn dead players (and remove dead NPCs) once they've been dead for long enough
+    fn handle_deaths(&mut self) {
+        let time = self.state.get_time();
+        let respawns = (
+            &self.state.ecs().internal().entities(),
+            &self.state.ecs().internal().read_storage::<comp::Dead>(),
+            self.state.ecs().internal().read_storage::<comp::Player>().maybe(),
+        )
+            .join()
+            .filter(|(_, dead, _)| time - dead.time_of_death > RESPAWN_DELAY)
+            .map(|(entity, _, player)| (entity, player.is_some()))
+            .collect::<Vec<_>>();
+
+        for (entity, is_player) in respawns {
+            if !is_player {
+                self.dismount(entity);
+                self.state.ecs_mut().delete_entity_synced(entity);
+                continue;
+            }
+
+            let max_health = self
+                .state
+                .read_component_cloned::<comp::Stats>(entity)
+                .map(|stats| stats.max_health)
+                .unwrap_or(CHARACTER_HEALTH);
+
+            self.state.ecs_mut().internal_mut().write_storage::<comp::Dead>().remove(entity);
+            self.state.write_component(entity, comp::Stats::new(max_health));
+            self.state.write_component(entity, comp::phys::Pos(SPAWN_POS));
+            self.state.write_component(entity, comp::phys::Vel(Vec3::zero()));
+            self.state.write_component(entity, comp::phys::ForceUpdate);
+            self.state.write_component(entity, comp::AnimationHistory {
+                last: None,
+                current: Animation::Idle,
+            });
+        }
+    }
+
+    /// Spawn NPCs into the loaded regions around players according to the spawn rules, and
+    /// despawn the ones that nobody is near any more.
+    fn update_population(&mut self) {
+        self.last_spawn = self.state.get_time();
+        let mut rng = rand::thread_rng();
+
+        let ecs = self.state.ecs().internal();
+        let player_positions = (
+            &ecs.read_storage::<comp::Player>(),
+            &ecs.read_storage::<comp::phys::Pos>(),
+        )
+            .join()
+            .map(|(_, pos)| pos.0)
+            .collect::<Vec<_>>();
+        let near_player = |pos: Vec3<f32>, dist: f32| player_positions.iter().any(|p| p.distance(pos) < dist);
+
+        let terrain = self.state.terrain();
+
+        // Count the NPCs in each region, despawning those that are far from players or have
+        // wandered out of the loaded terrain
+        let mut populations: HashMap<Vec3<i32>, HashMap<usize, usize>> = HashMap::new();
+        let mut despawns = Vec::new();
+        for (entity, spawned, pos) in (
+            &ecs.entities(),
+            &ecs.read_storage::<Spawned>(),
+            &ecs.read_storage::<comp::phys::Pos>(),
+        )
+            .join()
+        {
+            let region = spawn::region_key(pos.0);
+            if !near_player(pos.0, DESPAWN_DISTANCE) || terrain.get_key(region).is_none() {
+                despawns.push(entity);
+            } else {
+                *populations.entry(region).or_default().entry(spawned.rule).or_insert(0) += 1;
+            }
+        }
+
+        // Top up the populations of the loaded regions near players
+        let player_regions = player_positions.iter().map(|pos| spawn::region_key(*pos)).collect::<Vec<_>>();
+        let is_solid = |pos: Vec3<i32>| terrain.get(pos).map(|block| !block.is_empty()).unwrap_or(false);
+        let chunk_size = TerrainMap::chunk_size().map(|e| e as i32);
+        let time_of_day = self.state.get_time_of_day();
+        let mut spawns = Vec::new();
+        for (key, chunk) in terrain.iter() {
+            let nearby = player_regions.iter().any(|region| {
+                let offs = (key - *region).map(|e| e.abs());
+                offs.x.max(offs.y) <= SPAWN_RADIUS && offs.z <= 1
+            });
+            if !nearby {
+                continue;
+            }
+
+            let population = populations.get(&key).cloned().unwrap_or_default();
+            for group in self.spawn_rules.plan(&mut rng, key, chunk.metadata().biome(), time_of_day, &population) {
+                // Look for the ground in a random column of the region
+                let column = terrain.key_pos(key)
+                    + Vec3::new(rng.gen_range(0, chunk_size.x), rng.gen_range(0, chunk_size.y), 0);
+                let ground = (0..chunk_size.z)
+                    .rev()
+                    .map(|z| column + Vec3::unit_z() * z)
+                    .find(|pos| path::is_walkable(&is_solid, *pos));
+                let ground = match ground {
+                    Some(ground) => ground,
+                    None => continue,
+                };
+                let cell_pos = |cell: Vec3<i32>| cell.map(|e| e as f32) + Vec3::new(0.5, 0.5, 0.0);
+
+                // Don't let NPCs appear right in front of players
+                if near_player(cell_pos(ground), MIN_SPAWN_DISTANCE) {
+                    continue;
+                }
+
+                // Scatter the rest of the group around the first member, on the ground near its
+                // height, or right next to it if there isn't any
+                for i in 0..group.count {
+                    let cell = if i == 0 {
+                        ground
+                    } else {
+                        let column = ground + Vec3::new(
+                            rng.gen_range(-GROUP_SPREAD, GROUP_SPREAD + 1),
+                            rng.gen_range(-GROUP_SPREAD, GROUP_SPREAD + 1),
+                            0,
+                        );
+                        (-GROUP_SPREAD..GROUP_SPREAD + 1)
+                            .rev()
+                            .map(|dz| column + Vec3::unit_z() * dz)
+                            .find(|pos| path::is_walkable(&is_solid, *pos))
+                            .unwrap_or(ground)
+                    };
+                    spawns.push((group.rule, cell_pos(cell)));
+                }
+            }
+        }
+
+        drop(terrain);
+
+        for entity in despawns {
+            self.dismount(entity);
+            self.state.ecs_mut().delete_entity_synced(entity);
+        }
+
+        for (rule, pos) in spawns {
+            let creature = match self.spawn_rules.get(rule) {
+                Some(rule) => rule.creature,
+                None => continue,
+            };
+
+            self.create_npc(comp::phys::Pos(pos), creature.body(&mut rng))
+                .with(comp::Agent::new(creature.behaviour.into(), Vec2::from(pos)))
+                .with(comp::Control::default())
+                .with(Spawned { rule })
+                .build();
+        }
+    }
+
+    /// Send the current weather to all clients
+    fn sync_weather(&mut self) {
+        self.clients.notify_connected(ServerMsg::Weather(self.state.weather().compress()));
+        self.last_weather_sync = self.state.get_time();
+    }
+
+    /// Force the weather everywhere towards the given weather (or return to the natural weather
+    /// if `None`).
+    #[allow(dead_code)]
+    pub fn set_forced_weather(&mut self, weather: Option<common::weather::Weather>) {
+        self.weather_sim.set_forced(weather);
+    }
+
     /// Sync client states with the most up to date information
     fn sync_clients(&mut self) {
         // Sync 'logical' state using Sphynx
@@ -432,13 +1042,349 @@ impl Server {
 
         // Remove all force flags
         self.state.ecs_mut().internal_mut().write_storage::<comp::phys::ForceUpdate>().clear();
+
+        // Periodically send the latest weather
+        if self.state.get_time() - self.last_weather_sync > WEATHER_SYNC_INTERVAL {
+            self.sync_weather();
+        }
+
+        // Periodically correct any drift in clients' time of day
+        if self.state.get_time() - self.last_time_sync > TIME_SYNC_INTERVAL {
+            self.sync_time_of_day();
+        }
+    }
+
+    /// Validate an inventory operation requested by a player and apply it if it's allowed. The
+    /// player is sent their updated inventory if anything changed.
+    fn handle_inventory_action(&mut self, entity: EcsEntity, action: InventoryAction) {
+        // Dead characters can't use their inventory
+        if self.state.read_storage::<comp::Dead>().get(entity).is_some() {
+            return;
+        }
+        let mut inventory = match self.state.read_component_cloned::<comp::Inventory>(entity) {
+            Some(inventory) => inventory,
+            None => return,
+        };
+
+        let changed = match action {
+            InventoryAction::Move { from, to } => inventory.move_slot(from, to, &self.items).is_ok(),
+            InventoryAction::Split { slot, amount } => inventory.split(slot, amount).is_ok(),
+            InventoryAction::Drop { slot } => match (
+                self.state.read_component_cloned::<comp::phys::Pos>(entity),
+                inventory.remove(slot),
+            ) {
+                (Some(pos), Ok(stack)) => {
+                    self.state
+                        .ecs_mut()
+                        .create_entity_synced()
+                        .with(pos)
+                        .with(comp::phys::Vel(Vec3::zero()))
+                        .with(comp::phys::Dir(Vec3::unit_y()))
+                        .with(comp::ItemDrop(stack))
+                        .build();
+                    true
+                }
+                _ => false,
+            },
+            InventoryAction::PickUp { item_entity } => {
+                let item_entity = match self.state.ecs().entity_from_uid(item_entity) {
+                    Some(item_entity) => item_entity,
+                    None => return,
+                };
+                let (stack, item_pos, player_pos) = match (
+                    self.state.read_component_cloned::<comp::ItemDrop>(item_entity),
+                    self.state.read_component_cloned::<comp::phys::Pos>(item_entity),
+                    self.state.read_component_cloned::<comp::phys::Pos>(entity),
+                ) {
+                    (Some(comp::ItemDrop(stack)), Some(item_pos), Some(player_pos)) => (stack, item_pos, player_pos),
+                    _ => return,
+                };
+
+                if item_pos.0.distance(player_pos.0) > PICKUP_REACH {
+                    return;
+                }
+
+                match inventory.insert(stack, &self.items) {
+                    None => self.state.ecs_mut().delete_entity_synced(item_entity),
+                    // Whatever didn't fit stays on the ground
+                    Some(leftover) if leftover != stack => self.state.write_component(item_entity, comp::ItemDrop(leftover)),
+                    Some(_) => return,
+                }
+                true
+            }
+        };
+
+        if changed {
+            self.state.write_component(entity, inventory.clone());
+            self.clients.notify(entity, ServerMsg::InventoryUpdate(inventory));
+        }
+    }
+
+    /// Validate a player's request to ride a creature and seat them on it if it's allowed. Only
+    /// tameable creatures can be ridden, and riding one tames it, so it follows its rider around
+    /// after they dismount.
+    fn mount(&mut self, entity: EcsEntity, mount_uid: u64) {
+        let mount = match self.state.ecs().entity_from_uid(mount_uid) {
+            Some(mount) if mount != entity => mount,
+            _ => return,
+        };
+        let rider_uid: u64 = match self.state.ecs().uid_from_entity(entity) {
+            Some(uid) => uid.into(),
+            None => return,
+        };
+
+        {
+            let alive = |entity: EcsEntity| self.state
+                .read_storage::<comp::Stats>()
+                .get(entity)
+                .map(|stats| !stats.is_dead())
+                .unwrap_or(false);
+            // Nobody can be part of two riding relationships at once
+            let unattached = |entity: EcsEntity| {
+                self.state.read_storage::<comp::Mount>().get(entity).is_none() &&
+                self.state.read_storage::<comp::Rider>().get(entity).is_none()
+            };
+            let rideable = self.state
+                .read_storage::<comp::Body>()
+                .get(mount)
+                .and_then(|body| body.mount_offset())
+                .is_some();
+            let hostile = self.state
+                .read_storage::<comp::Agent>()
+                .get(mount)
+                .map(|agent| agent.temperament == comp::Temperament::Hostile)
+                .unwrap_or(false);
+            let in_reach = match (
+                self.state.read_storage::<comp::phys::Pos>().get(entity),
+                self.state.read_storage::<comp::phys::Pos>().get(mount),
+            ) {
+                (Some(rider_pos), Some(mount_pos)) => rider_pos.0.distance(mount_pos.0) <= MOUNT_REACH,
+                _ => false,
+            };
+
+            if !alive(entity) || !alive(mount) || !unattached(entity) || !unattached(mount) ||
+                !rideable || hostile || !in_reach
+            {
+                return;
+            }
+        }
+
+        self.state.write_component(mount, comp::Mount { rider: rider_uid });
+        self.state.write_component(entity, comp::Rider { mount: mount_uid });
+        if let Some(agent) = self.state.ecs().internal().write_storage::<comp::Agent>().get_mut(mount) {
+            agent.temperament = comp::Temperament::Companion(entity);
+        }
+    }
+
+    /// End any riding relationship the entity is part of, whether as the rider or as the mount.
+    fn dismount(&mut self, entity: EcsEntity) {
+        mount::dismount(&mut self.state, entity);
+    }
+
+    /// Whether a player has admin rights on this server.
+    pub fn is_admin(&self, entity: EcsEntity) -> bool {
+        self.state.read_storage::<Admin>().get(entity).is_some()
+    }
+
+    /// Whether the server settings allow a player to modify terrain.
+    fn can_build(&self, entity: EcsEntity) -> bool {
+        match self.settings.build_permission {
+            BuildPermission::Everyone => true,
+            BuildPermission::Admins => self.is_admin(entity),
+            BuildPermission::Nobody => false,
+        }
+    }
+
+    /// Validate a block modification requested by a player and apply it if it's allowed.
+    /// Returns whether the modification was applied.
+    fn modify_block(&mut self, entity: EcsEntity, pos: Vec3<i32>, block: Block) -> bool {
+        if !self.can_build(entity) {
+            return false;
+        }
+
+        // Only players with a living character in the world may modify terrain
+        let player_pos = match (
+            self.state.read_storage::<comp::Body>().get(entity),
+            self.state.read_storage::<comp::phys::Pos>().get(entity),
+            self.state.read_storage::<comp::Stats>().get(entity),
+        ) {
+            (Some(_), Some(player_pos), Some(stats)) if !stats.is_dead() => player_pos.0,
+            _ => return false,
+        };
+
+        // The block must be within reach of the player
+        if pos.map(|e| e as f32 + 0.5).distance(player_pos) > BLOCK_REACH {
+            return false;
+        }
+
+        // Fails if the chunk containing the block doesn't exist
+        self.state.set_block(pos, block).is_ok()
+    }
+
+    /// Notify clients that have a chunk about modifications to the blocks at the provided world
+    /// positions within it, sending either a delta or the whole chunk, whichever is smaller.
+    fn send_chunk_changes(&mut self, key: Vec3<i32>, positions: Vec<Vec3<i32>>) {
+        let base_version = self.chunk_versions.get(&key).cloned().unwrap_or(0);
+
+        let terrain = self.state.terrain();
+        let chunk = match terrain.get_key(key) {
+            Some(chunk) => chunk,
+            None => return,
+        };
+
+        let chunk_pos = terrain.key_pos(key);
+        let delta = match chunk.delta_for(positions.into_iter().map(|pos| pos - chunk_pos)) {
+            Ok(delta) => delta,
+            Err(err) => {
+                warn!("Failed to build delta for chunk at {:?}: {:?}", key, err);
+                return;
+            },
+        };
+
+        // Finding the exact size of the chunk means walking all of it, so only do that when the
+        // delta isn't clearly smaller
+        let delta_size = bincode::serialized_size(&delta).unwrap_or(u64::max_value());
+        let delta_is_smaller = delta_size < chunk.min_serialized_size() as u64 ||
+            delta_size < bincode::serialized_size(chunk).unwrap_or(u64::max_value());
+
+        let msg = if delta_is_smaller {
+            ServerMsg::TerrainChunkDelta {
+                key,
+                base_version,
+                delta,
+            }
+        } else {
+            ServerMsg::TerrainChunkUpdate {
+                key,
+                version: base_version + 1,
+                chunk: Box::new(chunk.clone()),
+            }
+        };
+        drop(terrain);
+
+        // Only move the version on once there's something to tell clients, otherwise their next
+        // delta wouldn't apply
+        self.chunk_versions.insert(key, base_version + 1);
+        self.clients.notify_connected_with_chunk(key, msg);
+    }
+
+    /// Load the persisted data for an account, or empty data if the account has none yet. Errors
+    /// are logged, and must not be treated as a new account or the stored data would be lost.
+    fn load_player(persistence: &dyn PersistenceBackend, account: &str) -> Result<PlayerData, &'static str> {
+        match persistence.load_player(account) {
+            Ok(data) => Ok(data.unwrap_or_default()),
+            Err(err) => {
+                warn!("Failed to load player data for '{}': {:?}", account, err);
+                Err("Failed to load characters")
+            }
+        }
+    }
+
+    /// Store the persisted data for an account, logging any errors
+    fn store_player(&mut self, account: &str, data: &PlayerData) -> Result<(), &'static str> {
+        self.persistence.save_player(account, data).map_err(|err| {
+            warn!("Failed to save player data for '{}': {:?}", account, err);
+            "Failed to save character"
+        })
+    }
+
+    /// Persist the data of the character played by the given entity, if it has one
+    fn save_player(&mut self, entity: EcsEntity) {
+        let ecs = self.state.ecs().internal();
+        let (player, name, character, pos, dir, stats, inventory) = match (
+            ecs.read_storage::<comp::Player>().get(entity).cloned(),
+            ecs.read_storage::<CharacterName>().get(entity).cloned(),
+            ecs.read_storage::<comp::Body>().get(entity).and_then(|body| body.humanoid().cloned()),
+            ecs.read_storage::<comp::phys::Pos>().get(entity).cloned(),
+            ecs.read_storage::<comp::phys::Dir>().get(entity).cloned(),
+            ecs.read_storage::<comp::Stats>().get(entity).cloned(),
+            ecs.read_storage::<comp::Inventory>().get(entity).cloned(),
+        ) {
+            (Some(player), Some(CharacterName(name)), Some(character), Some(pos), Some(dir), Some(stats), Some(inventory)) =>
+                (player, name, character, pos, dir, stats, inventory),
+            // Chat-only clients and players still picking a character have nothing worth saving
+            _ => return,
+        };
+
+        let mut data = match Self::load_player(&*self.persistence, &player.alias) {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        // Don't bring back a character that was deleted in the meantime
+        if data.character(&name).is_none() {
+            return;
+        }
+        data.set_character(CharacterData { name, character, pos, dir, stats, inventory });
+        let _ = self.store_player(&player.alias, &data);
+    }
+
+    /// Persist the data of every connected player
+    fn save_all_players(&mut self) {
+        let players = (
+            &self.state.ecs().internal().entities(),
+            &self.state.ecs().internal().read_storage::<comp::Player>(),
+        )
+            .join()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+
+        for entity in players {
+            self.save_player(entity);
+        }
+    }
+
+    /// Save all modified chunks to disk in the background
+    fn save_dirty_chunks(&mut self) {
+        // Don't start a new save while one is still running, otherwise an older save could
+        // finish after a newer one and overwrite it. The dirty chunks will be picked up next time.
+        if self.dirty_chunks.is_empty()
+            || self.chunk_save_in_progress.swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+
+        let mut terrain = self.state.ecs_mut().internal_mut().write_resource::<TerrainMap>();
+        let chunks = self
+            .dirty_chunks
+            .drain()
+            .filter_map(|key| terrain.get_key_mut(key).map(|chunk| {
+                // Edits leave behind palette entries for blocks that are gone, so drop them both
+                // from the saved chunk and from memory
+                chunk.shrink();
+                (key, chunk.clone())
+            }))
+            .collect::<Vec<_>>();
+        drop(terrain);
+
+        let chunk_store = self.chunk_store.clone();
+        let in_progress = self.chunk_save_in_progress.clone();
+        let unsaved_chunk_tx = self.unsaved_chunk_tx.clone();
+        self.thread_pool.execute(move || {
+            let keys = chunks.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+            if let Err(err) = chunk_store.save_chunks(chunks) {
+                warn!("Failed to save modified chunks, they will be retried: {:?}", err);
+                let _ = unsaved_chunk_tx.send(keys);
+            }
+            in_progress.store(false, Ordering::SeqCst);
+        });
     }
 
     pub fn generate_chunk(&mut self, key: Vec3<i32>) {
         if self.pending_chunks.insert(key) {
             let chunk_tx = self.chunk_tx.clone();
-            self.thread_pool
-                .execute(move || chunk_tx.send((key, World::generate_chunk(key))).unwrap());
+            let chunk_store = self.chunk_store.clone();
+            self.thread_pool.execute(move || {
+                // Prefer modified chunks saved on disk over freshly generated ones
+                let chunk = match chunk_store.load_chunk(key) {
+                    Ok(Some(chunk)) => chunk,
+                    Ok(None) => World::generate_chunk(key),
+                    Err(err) => {
+                        warn!("Failed to load chunk at {:?}: {:?}", key, err);
+                        World::generate_chunk(key)
+                    }
+                };
+                chunk_tx.send((key, chunk)).unwrap();
+            });
         }
     }
 
@@ -470,6 +1416,13 @@ impl Server {
 
 impl Drop for Server {
     fn drop(&mut self) {
+        self.save_all_players();
+
+        // Wait for any in-progress save to finish before flushing the remaining modified chunks
+        self.thread_pool.join();
+        self.save_dirty_chunks();
+        self.thread_pool.join();
+
         self.clients.notify_connected(ServerMsg::Shutdown);
     }
 }
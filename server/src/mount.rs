@@ -0,0 +1,112 @@
+//! # Riding
+//! Riding relationships are stored on both sides (`Mount` on the creature, `Rider` on the player),
+//! so they have to be ended on both sides together, before either of them dies or goes away.
+
+use common::{comp, state::State};
+use specs::Entity as EcsEntity;
+
+/// End any riding relationship the entity is part of, whether as the rider or as the mount.
+pub fn dismount(state: &mut State, entity: EcsEntity) {
+    let (rider, mount) = match (
+        state.read_component_cloned::<comp::Rider>(entity),
+        state.read_component_cloned::<comp::Mount>(entity),
+    ) {
+        (Some(comp::Rider { mount }), _) => (Some(entity), state.ecs().entity_from_uid(mount)),
+        (_, Some(comp::Mount { rider })) => (state.ecs().entity_from_uid(rider), Some(entity)),
+        _ => return,
+    };
+
+    let ecs = state.ecs_mut().internal_mut();
+    if let Some(rider) = rider {
+        ecs.write_storage::<comp::Rider>().remove(rider);
+        // Players only have controls on the server while they're steering a mount
+        if ecs.read_storage::<comp::Agent>().get(rider).is_none() {
+            ecs.write_storage::<comp::Control>().remove(rider);
+        }
+    }
+    if let Some(mount) = mount {
+        ecs.write_storage::<comp::Mount>().remove(mount);
+        // Don't let the mount carry on with its rider's last input
+        if let Some(control) = ecs.write_storage::<comp::Control>().get_mut(mount) {
+            *control = comp::Control::default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::comp::body::quadruped;
+    use specs::Builder;
+    use vek::*;
+
+    /// A player riding a boar, returning the player and the boar.
+    fn riding(state: &mut State) -> (EcsEntity, EcsEntity) {
+        let mount = state
+            .ecs_mut()
+            .create_entity_synced()
+            .with(comp::Body::Quadruped(quadruped::Body::new(quadruped::Species::Boar)))
+            .with(comp::Agent::new(comp::Temperament::Passive, Vec2::zero()))
+            .build();
+        let rider = state
+            .ecs_mut()
+            .create_entity_synced()
+            .with(comp::Control {
+                move_dir: Vec2::unit_x(),
+                ..comp::Control::default()
+            })
+            .build();
+
+        let mount_uid = state.ecs().uid_from_entity(mount).unwrap().into();
+        let rider_uid = state.ecs().uid_from_entity(rider).unwrap().into();
+        state.write_component(mount, comp::Mount { rider: rider_uid });
+        state.write_component(rider, comp::Rider { mount: mount_uid });
+        state.write_component(mount, comp::Control {
+            move_dir: Vec2::unit_x(),
+            ..comp::Control::default()
+        });
+        (rider, mount)
+    }
+
+    fn assert_unattached(state: &State, rider: EcsEntity, mount: EcsEntity) {
+        assert!(state.read_storage::<comp::Rider>().get(rider).is_none());
+        assert!(state.read_storage::<comp::Mount>().get(mount).is_none());
+        assert!(state.read_storage::<comp::Control>().get(rider).is_none());
+        let control = state.read_component_cloned::<comp::Control>(mount).unwrap();
+        assert_eq!(control.move_dir, Vec2::zero());
+    }
+
+    #[test]
+    fn dying_mount_throws_its_rider() {
+        let mut state = State::new();
+        let (rider, mount) = riding(&mut state);
+
+        dismount(&mut state, mount);
+
+        assert_unattached(&state, rider, mount);
+    }
+
+    #[test]
+    fn dying_rider_frees_its_mount() {
+        let mut state = State::new();
+        let (rider, mount) = riding(&mut state);
+
+        dismount(&mut state, rider);
+
+        assert_unattached(&state, rider, mount);
+    }
+
+    #[test]
+    fn disconnecting_rider_frees_its_mount() {
+        let mut state = State::new();
+        let (rider, mount) = riding(&mut state);
+
+        // What the server does when a client disconnects
+        dismount(&mut state, rider);
+        state.ecs_mut().delete_entity_synced(rider);
+
+        assert!(state.read_storage::<comp::Mount>().get(mount).is_none());
+        // Nothing is left to end
+        dismount(&mut state, mount);
+    }
+}
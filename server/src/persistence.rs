@@ -0,0 +1,290 @@
+//! # Player persistence
+//! Player data (each of the account's characters, with their position, stats, inventory, etc.) is stored
+//! per account so that it survives disconnects and server restarts. Storage is abstracted behind the `Backend` trait so that
+//! different storage mechanisms can be swapped in without touching the rest of the server.
+
+use std::{
+    fs,
+    io,
+    path::PathBuf,
+};
+use serde_derive::{Serialize, Deserialize};
+use specs::{Component, VecStorage};
+use common::comp;
+
+pub const MAX_CHARACTERS: usize = 8; // Per account
+pub const MAX_CHARACTER_NAME_LEN: usize = 24; // Characters
+
+/// The version of the format player data is stored in. Bump this whenever a change to
+/// `PlayerData` (or anything in it) breaks deserialization, and convert data saved in older
+/// formats when loading it.
+pub const PLAYER_DATA_VERSION: u32 = 1;
+
+// Marks files written by `FileBackend`, so that data saved before formats were versioned isn't
+// mistaken for a version number
+const FILE_MAGIC: &[u8; 4] = b"VPLR";
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Serialization(bincode::Error),
+    /// The stored data isn't in a format this version of the server knows about
+    UnsupportedFormat(Option<u32>),
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<bincode::Error> for Error {
+    fn from(err: bincode::Error) -> Self {
+        Error::Serialization(err)
+    }
+}
+
+/// Everything about a character that is kept between sessions.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CharacterData {
+    /// The name of the character, unique within its account
+    pub name: String,
+    pub character: comp::Character,
+    pub pos: comp::phys::Pos,
+    pub dir: comp::phys::Dir,
+    pub stats: comp::Stats,
+    pub inventory: comp::Inventory,
+}
+
+/// Everything about a player that is kept between sessions.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PlayerData {
+    pub characters: Vec<CharacterData>,
+}
+
+impl PlayerData {
+    pub fn character(&self, name: &str) -> Option<&CharacterData> {
+        self.characters.iter().find(|data| data.name == name)
+    }
+
+    /// Store the data of a character, replacing any existing character with the same name.
+    pub fn set_character(&mut self, data: CharacterData) {
+        match self.characters.iter_mut().find(|existing| existing.name == data.name) {
+            Some(existing) => *existing = data,
+            None => self.characters.push(data),
+        }
+    }
+
+    /// Check whether a new character called `name` (already trimmed) can be added to the account.
+    pub fn can_create_character(&self, name: &str, character: &comp::Character) -> Result<(), &'static str> {
+        if name.is_empty() || name.chars().count() > MAX_CHARACTER_NAME_LEN {
+            Err("Invalid character name")
+        } else if !character.is_valid() {
+            Err("Invalid character")
+        } else if self.character(name).is_some() {
+            Err("A character with that name already exists")
+        } else if self.characters.len() >= MAX_CHARACTERS {
+            Err("Too many characters")
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Remove a character, returning whether it existed.
+    pub fn remove_character(&mut self, name: &str) -> bool {
+        let len = self.characters.len();
+        self.characters.retain(|data| data.name != name);
+        self.characters.len() != len
+    }
+}
+
+/// Attached to player entities to record which of the account's characters they're playing, so
+/// that it can be saved back to the right place.
+#[derive(Clone, Debug)]
+pub struct CharacterName(pub String);
+
+impl Component for CharacterName {
+    type Storage = VecStorage<Self>;
+}
+
+/// A storage mechanism for player data, keyed by account name.
+pub trait Backend: Send {
+    /// Load the data stored for the given account, if there is any. Data that exists but can't be
+    /// loaded is an error rather than `None`, so that callers don't overwrite it.
+    fn load_player(&self, account: &str) -> Result<Option<PlayerData>, Error>;
+
+    /// Store the data for the given account, replacing whatever was there before.
+    fn save_player(&mut self, account: &str, data: &PlayerData) -> Result<(), Error>;
+}
+
+/// A backend that stores each account's data in its own file within a directory.
+pub struct FileBackend {
+    dir: PathBuf,
+}
+
+impl FileBackend {
+    /// Create a new `FileBackend` storing files in `dir`, creating the directory if needed.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Result<Self, Error> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Account names are chosen by players, so hex-encode them to get a safe file name.
+    fn path_for(&self, account: &str) -> PathBuf {
+        let name = account
+            .bytes()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        self.dir.join(format!("{}.dat", name))
+    }
+}
+
+impl Backend for FileBackend {
+    fn load_player(&self, account: &str) -> Result<Option<PlayerData>, Error> {
+        match fs::read(self.path_for(account)) {
+            Ok(bytes) => {
+                if !bytes.starts_with(FILE_MAGIC) {
+                    return Err(Error::UnsupportedFormat(None));
+                }
+                let bytes = &bytes[FILE_MAGIC.len()..];
+                match bincode::deserialize::<u32>(bytes)? {
+                    PLAYER_DATA_VERSION => {
+                        let (_, data): (u32, PlayerData) = bincode::deserialize(bytes)?;
+                        Ok(Some(data))
+                    },
+                    version => Err(Error::UnsupportedFormat(Some(version))),
+                }
+            },
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn save_player(&mut self, account: &str, data: &PlayerData) -> Result<(), Error> {
+        let path = self.path_for(account);
+        let tmp_path = path.with_extension("tmp");
+
+        // Write to a temporary file first so that a crash mid-write can't corrupt existing data
+        let mut bytes = FILE_MAGIC.to_vec();
+        bytes.extend(bincode::serialize(&(PLAYER_DATA_VERSION, data))?);
+        fs::write(&tmp_path, bytes)?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::comp::character::{Character, Gender, Race, Weapon};
+    use vek::*;
+
+    fn temp_backend(name: &str) -> FileBackend {
+        let dir = std::env::temp_dir().join(format!("veloren-persistence-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        FileBackend::new(dir).unwrap()
+    }
+
+    fn character_data(name: &str) -> CharacterData {
+        let mut stats = comp::Stats::new(100);
+        stats.damage(40);
+        CharacterData {
+            name: String::from(name),
+            character: Character::new(Race::Human, Gender::Female, Weapon::Sword),
+            pos: comp::phys::Pos(Vec3::new(1.0, -2.0, 30.0)),
+            dir: comp::phys::Dir(Vec3::unit_x()),
+            stats,
+            inventory: comp::Inventory::default(),
+        }
+    }
+
+    #[test]
+    fn file_backend_round_trip() {
+        let mut backend = temp_backend("round-trip");
+        assert!(backend.load_player("nobody").unwrap().is_none());
+
+        let mut data = PlayerData::default();
+        data.set_character(character_data("Ada"));
+        // Account names may contain characters that aren't valid in file names
+        backend.save_player("some/player", &data).unwrap();
+
+        let loaded = backend.load_player("some/player").unwrap().unwrap();
+        let loaded = loaded.character("Ada").unwrap();
+        assert_eq!(loaded.character, data.characters[0].character);
+        assert_eq!(loaded.pos.0, data.characters[0].pos.0);
+        assert_eq!(loaded.stats, data.characters[0].stats);
+        assert_eq!(loaded.inventory, data.characters[0].inventory);
+
+        let _ = fs::remove_dir_all(&backend.dir);
+    }
+
+    #[test]
+    fn unreadable_data_is_an_error() {
+        let backend = temp_backend("unreadable");
+
+        // Data saved before formats were versioned
+        fs::write(backend.path_for("old"), bincode::serialize(&PlayerData::default()).unwrap()).unwrap();
+        match backend.load_player("old") {
+            Err(Error::UnsupportedFormat(None)) => {},
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        // Data saved by a newer server
+        let mut bytes = FILE_MAGIC.to_vec();
+        bytes.extend(bincode::serialize(&(PLAYER_DATA_VERSION + 1, PlayerData::default())).unwrap());
+        fs::write(backend.path_for("new"), bytes).unwrap();
+        match backend.load_player("new") {
+            Err(Error::UnsupportedFormat(Some(version))) => assert_eq!(version, PLAYER_DATA_VERSION + 1),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let _ = fs::remove_dir_all(&backend.dir);
+    }
+
+    #[test]
+    fn set_character_replaces_by_name() {
+        let mut data = PlayerData::default();
+        data.set_character(character_data("Ada"));
+        data.set_character(character_data("Bob"));
+
+        let mut moved = character_data("Ada");
+        moved.pos = comp::phys::Pos(Vec3::new(5.0, 5.0, 50.0));
+        data.set_character(moved);
+
+        assert_eq!(data.characters.len(), 2);
+        assert_eq!(data.character("Ada").unwrap().pos.0, Vec3::new(5.0, 5.0, 50.0));
+    }
+
+    #[test]
+    fn remove_character_reports_whether_it_existed() {
+        let mut data = PlayerData::default();
+        data.set_character(character_data("Ada"));
+
+        assert!(!data.remove_character("Bob"));
+        assert!(data.remove_character("Ada"));
+        assert!(data.character("Ada").is_none());
+        assert!(!data.remove_character("Ada"));
+    }
+
+    #[test]
+    fn create_rules() {
+        let character = Character::new(Race::Elf, Gender::Male, Weapon::Bow);
+        let mut data = PlayerData::default();
+        assert!(data.can_create_character("Ada", &character).is_ok());
+
+        assert!(data.can_create_character("", &character).is_err());
+        let long_name = "a".repeat(MAX_CHARACTER_NAME_LEN + 1);
+        assert!(data.can_create_character(&long_name, &character).is_err());
+        assert!(data.can_create_character(&long_name[1..], &character).is_ok());
+        assert!(data.can_create_character("Ada", &Character { hair_style: 255, ..character }).is_err());
+
+        data.set_character(character_data("Ada"));
+        assert!(data.can_create_character("Ada", &character).is_err());
+
+        for i in 1..MAX_CHARACTERS {
+            data.set_character(character_data(&format!("Ada{}", i)));
+        }
+        assert!(data.can_create_character("Bob", &character).is_err());
+    }
+}
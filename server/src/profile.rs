@@ -0,0 +1,200 @@
+//! # Tick profiling
+//! The server records how long each phase of a tick (and each ECS system) takes. A rolling window
+//! of recent ticks is kept in memory so that a summary can be requested at any time, and the raw
+//! spans can optionally be streamed to a file in the Chrome trace event format (viewable with
+//! `chrome://tracing`) for offline analysis.
+
+use common::state::SysTiming;
+use log::warn;
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// The number of recent ticks used to compute summaries.
+const HISTORY_LEN: usize = 300;
+
+/// Traces stop on their own after this many events (a few tens of megabytes), so a forgotten
+/// trace can't fill the disk.
+pub const MAX_TRACE_EVENTS: usize = 250_000;
+
+/// The name of the span covering a whole tick.
+pub const TICK_SPAN: &str = "tick";
+
+/// A single timed section of a tick.
+#[derive(Clone, Debug)]
+struct Span {
+    name: &'static str,
+    // Relative to the start of the profiler
+    start: Duration,
+    dur: Duration,
+    // The trace 'thread' the span is displayed on. Tick phases run on the main thread (0), ECS
+    // systems may run in parallel so each gets its own.
+    tid: usize,
+}
+
+/// Statistics about the duration of a span over recent ticks.
+#[derive(Copy, Clone, Debug)]
+pub struct SpanStats {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+pub struct Profiler {
+    epoch: Instant,
+    tick_start: Option<Instant>,
+    current: Vec<Span>,
+    history: VecDeque<Vec<Span>>,
+    trace: Option<BufWriter<File>>,
+    trace_events: usize,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            tick_start: None,
+            current: Vec::new(),
+            history: VecDeque::with_capacity(HISTORY_LEN),
+            trace: None,
+            trace_events: 0,
+        }
+    }
+
+    /// Mark the start of a new tick.
+    pub fn begin_tick(&mut self) {
+        self.tick_start = Some(Instant::now());
+        self.current.clear();
+    }
+
+    /// Record a span of the current tick that started at `start` and ends now.
+    pub fn record(&mut self, name: &'static str, start: Instant) {
+        self.record_at(name, start, start.elapsed(), 0);
+    }
+
+    /// Record the time spent in ECS systems during the span starting at `start`. Systems that
+    /// didn't run are skipped.
+    pub fn record_systems(&mut self, start: Instant, timings: Vec<(&'static str, SysTiming)>) {
+        for (i, (name, timing)) in timings.into_iter().enumerate() {
+            if timing.runs > 0 {
+                self.record_at(name, start, timing.total, i + 1);
+            }
+        }
+    }
+
+    fn record_at(&mut self, name: &'static str, start: Instant, dur: Duration, tid: usize) {
+        self.current.push(Span {
+            name,
+            start: start.duration_since(self.epoch),
+            dur,
+            tid,
+        });
+    }
+
+    /// Mark the end of the current tick, adding it to the history (and the trace, if one is
+    /// being written).
+    pub fn end_tick(&mut self) {
+        if let Some(tick_start) = self.tick_start.take() {
+            self.record(TICK_SPAN, tick_start);
+        }
+
+        let spans = std::mem::replace(&mut self.current, Vec::new());
+
+        if let Err(err) = self.write_trace_events(&spans) {
+            warn!("Failed to write profiler trace, stopping trace: {:?}", err);
+            self.trace = None;
+        } else if self.trace_events >= MAX_TRACE_EVENTS {
+            if let Err(err) = self.stop_trace() {
+                warn!("Failed to finish profiler trace: {:?}", err);
+            }
+        }
+
+        if self.history.len() >= HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(spans);
+    }
+
+    /// Get statistics about each span over the recent ticks, sorted by name.
+    pub fn summary(&self) -> Vec<(&'static str, SpanStats)> {
+        let mut durs: HashMap<&'static str, Vec<Duration>> = HashMap::new();
+        for span in self.history.iter().flatten() {
+            durs.entry(span.name).or_insert_with(Vec::new).push(span.dur);
+        }
+
+        let mut summary = durs
+            .into_iter()
+            .map(|(name, mut durs)| {
+                durs.sort();
+                let percentile = |p: usize| durs[(durs.len() - 1) * p / 100];
+                (name, SpanStats {
+                    p50: percentile(50),
+                    p95: percentile(95),
+                    max: durs[durs.len() - 1],
+                })
+            })
+            .collect::<Vec<_>>();
+        summary.sort_by_key(|(name, _)| *name);
+        summary
+    }
+
+    /// Returns true if a trace is currently being written.
+    pub fn is_tracing(&self) -> bool {
+        self.trace.is_some()
+    }
+
+    /// Start writing spans to a Chrome trace file at the given path, replacing any trace that is
+    /// currently being written. The trace stops by itself after `MAX_TRACE_EVENTS` events.
+    pub fn start_trace<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        self.stop_trace()?;
+
+        let mut trace = BufWriter::new(File::create(path)?);
+        write!(trace, "[")?;
+        self.trace = Some(trace);
+        self.trace_events = 0;
+        Ok(())
+    }
+
+    /// Finish the trace currently being written, if any.
+    pub fn stop_trace(&mut self) -> io::Result<()> {
+        if let Some(mut trace) = self.trace.take() {
+            writeln!(trace, "\n]")?;
+            trace.flush()?;
+        }
+        Ok(())
+    }
+
+    fn write_trace_events(&mut self, spans: &[Span]) -> io::Result<()> {
+        let trace = match &mut self.trace {
+            Some(trace) => trace,
+            None => return Ok(()),
+        };
+
+        for span in spans {
+            // Span names are static identifiers, so they don't need escaping
+            write!(
+                trace,
+                "{}\n{{\"name\":\"{}\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":0,\"tid\":{}}}",
+                if self.trace_events > 0 { "," } else { "" },
+                span.name,
+                span.start.as_micros(),
+                span.dur.as_micros(),
+                span.tid,
+            )?;
+            self.trace_events += 1;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Profiler {
+    fn drop(&mut self) {
+        if let Err(err) = self.stop_trace() {
+            warn!("Failed to finish profiler trace: {:?}", err);
+        }
+    }
+}
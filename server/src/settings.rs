@@ -0,0 +1,78 @@
+//! # Server settings
+//! Operators configure the server through `server_settings.ron` in its working directory. The file
+//! is optional, and any setting missing from it takes its default value.
+
+use std::{
+    fs,
+    io,
+};
+use serde_derive::{Serialize, Deserialize};
+use specs::{Component, NullStorage};
+use common::state::DEFAULT_DAY_LENGTH;
+
+pub const SETTINGS_PATH: &str = "server_settings.ron";
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Parse(ron::de::Error),
+    /// A setting has a value that doesn't make sense
+    Invalid(&'static str),
+}
+
+/// Who is allowed to modify terrain.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuildPermission {
+    Everyone,
+    Admins,
+    Nobody,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerSettings {
+    /// The accounts that may use admin commands
+    pub admins: Vec<String>,
+    pub build_permission: BuildPermission,
+    /// How many real seconds an in-game day lasts
+    pub day_length: f64,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            admins: Vec::new(),
+            build_permission: BuildPermission::Everyone,
+            day_length: DEFAULT_DAY_LENGTH,
+        }
+    }
+}
+
+impl ServerSettings {
+    /// Load the settings file, falling back to the defaults if there isn't one.
+    pub fn load() -> Result<Self, Error> {
+        let settings: Self = match fs::read(SETTINGS_PATH) {
+            Ok(bytes) => ron::de::from_bytes(&bytes).map_err(Error::Parse)?,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(err) => return Err(Error::Io(err)),
+        };
+
+        if !(settings.day_length > 0.0 && settings.day_length.is_finite()) {
+            return Err(Error::Invalid("day_length must be a positive number of seconds"));
+        }
+        Ok(settings)
+    }
+
+    pub fn is_admin(&self, account: &str) -> bool {
+        self.admins.iter().any(|admin| admin == account)
+    }
+}
+
+/// Attached to the entities of players with admin rights. Rights are granted when a player
+/// connects, so changing alias afterwards doesn't grant (or revoke) them.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Admin;
+
+impl Component for Admin {
+    type Storage = NullStorage<Self>;
+}
@@ -16,31 +16,36 @@ uniform u_globals {
 	vec4 view_distance;
 	vec4 time_of_day;
 	vec4 tick;
+	vec4 sun_dir;
 };
 
 out vec4 tgt_color;
 
-const float PI = 3.141592;
-
-vec3 get_sky_color(vec3 dir, float time_of_day) {
-	const float TIME_FACTOR = (PI * 2.0) / (3600.0 * 24.0);
-
-	const vec3 SKY_TOP    = vec3(0.0, 0.3, 1.0);
-	const vec3 SKY_BOTTOM = vec3(0.0, 0.05, 0.2);
+vec3 get_sky_color(vec3 dir, vec3 sun_dir) {
+	const vec3 DAY_TOP      = vec3(0.0, 0.3, 1.0);
+	const vec3 DAY_BOTTOM   = vec3(0.0, 0.05, 0.2);
+	const vec3 NIGHT_TOP    = vec3(0.0, 0.0, 0.04);
+	const vec3 NIGHT_BOTTOM = vec3(0.0, 0.0, 0.01);
+	const vec3 DUSK_COLOR   = vec3(1.0, 0.35, 0.1);
 
 	const vec3 SUN_HALO_COLOR  = vec3(1.0, 0.8, 0.5);
 	const vec3 SUN_SURF_COLOR  = vec3(1.0, 0.8, 0.5);
 
-	float sun_angle_rad = time_of_day * TIME_FACTOR;
-	vec3 sun_dir = vec3(sin(sun_angle_rad), 0.0, cos(sun_angle_rad));
+	// 0 at night, 1 during the day, with a short transition while the sun crosses the horizon
+	float daylight = clamp(sun_dir.z * 4.0 + 0.5, 0.0, 1.0);
+	// Strongest when the sun is at the horizon, on the side of the sky that the sun is on
+	float dusk = (1.0 - abs(sun_dir.z)) * (1.0 - abs(sun_dir.z)) * max(dot(dir, normalize(vec3(sun_dir.xy, 0.0) + 0.0001)), 0.0);
+
+	vec3 sky_top = mix(NIGHT_TOP, DAY_TOP, daylight);
+	vec3 sky_bottom = mix(NIGHT_BOTTOM, DAY_BOTTOM, daylight) + DUSK_COLOR * dusk * 0.6;
 
 	vec3 sun_halo = pow(max(dot(dir, sun_dir), 0.0), 8.0) * SUN_HALO_COLOR;
 	vec3 sun_surf = min(pow(max(dot(dir, sun_dir), 0.0) + 0.01, 16.0), 1.0) * SUN_SURF_COLOR;
 	vec3 sun_light = sun_halo + sun_surf;
 
-	return mix(SKY_BOTTOM, SKY_TOP, (dir.z + 1.0) / 2.0) + sun_light * 0.5;
+	return mix(sky_bottom, sky_top, (dir.z + 1.0) / 2.0) + sun_light * 0.5;
 }
 
 void main() {
-	tgt_color = vec4(get_sky_color(normalize(f_pos), time_of_day.x), 1.0);
+	tgt_color = vec4(get_sky_color(normalize(f_pos), sun_dir.xyz), 1.0);
 }
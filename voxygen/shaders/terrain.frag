@@ -18,16 +18,18 @@ uniform u_globals {
 	vec4 view_distance;
 	vec4 time_of_day;
 	vec4 tick;
+	vec4 sun_dir;
 };
 
 out vec4 tgt_color;
 
 void main() {
-	float ambient = 0.5;
+	// Fade the sun's light out as it sets
+	float daylight = clamp(sun_dir.z * 4.0 + 0.5, 0.0, 1.0);
 
-	vec3 sun_dir = normalize(vec3(1.3, 1.7, 1.1));
+	float ambient = 0.2 + daylight * 0.3;
 
-	float sun_diffuse = dot(sun_dir, f_norm) * 0.5;
+	float sun_diffuse = max(dot(sun_dir.xyz, f_norm), 0.0) * 0.5 * daylight;
 
 	tgt_color = vec4(f_col * (ambient + sun_diffuse), 1.0);
 }
@@ -0,0 +1,49 @@
+// Library
+use vek::*;
+
+// Local
+use super::{
+    BirdSkeleton,
+    super::Animation,
+    SCALE,
+};
+
+pub struct IdleAnimation;
+
+impl Animation for IdleAnimation {
+    type Skeleton = BirdSkeleton;
+    type Dependency = f64;
+
+    fn update_skeleton(
+        skeleton: &Self::Skeleton,
+        time: f64,
+    ) -> Self::Skeleton {
+        let mut next = (*skeleton).clone();
+
+        let breathe = (time as f32 * 3.0).sin();
+        // Birds look around in short jerks rather than smoothly
+        let glance = ((time as f32 * 0.8).sin() * 2.0).round() * 0.3;
+
+        next.head.offset = Vec3::new(3.0, 0.0, 9.0 + breathe * 0.2) / SCALE;
+        next.head.ori = Quaternion::rotation_z(glance);
+
+        next.torso.offset = Vec3::new(0.0, 0.0, 5.0 + breathe * 0.2) / SCALE;
+        next.torso.ori = Quaternion::rotation_y(-0.2);
+
+        next.tail.offset = Vec3::new(-4.0, 0.0, 6.0) / SCALE;
+        next.tail.ori = Quaternion::rotation_y(-0.3);
+
+        // Wings folded against the body
+        next.l_wing.offset = Vec3::new(0.0, 3.0, 6.0) / SCALE;
+        next.l_wing.ori = Quaternion::rotation_x(-1.3);
+        next.r_wing.offset = Vec3::new(0.0, -3.0, 6.0) / SCALE;
+        next.r_wing.ori = Quaternion::rotation_x(1.3);
+
+        next.l_foot.offset = Vec3::new(0.0, 1.5, 3.0) / SCALE;
+        next.l_foot.ori = Quaternion::rotation_y(0.0);
+        next.r_foot.offset = Vec3::new(0.0, -1.5, 3.0) / SCALE;
+        next.r_foot.ori = Quaternion::rotation_y(0.0);
+
+        next
+    }
+}
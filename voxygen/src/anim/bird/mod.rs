@@ -0,0 +1,75 @@
+pub mod run;
+pub mod idle;
+
+// Reexports
+pub use self::run::RunAnimation;
+pub use self::idle::IdleAnimation;
+
+// Crate
+use crate::render::FigureBoneData;
+
+// Local
+use super::{
+    Skeleton,
+    Bone,
+};
+
+const SCALE: f32 = 11.0;
+
+#[derive(Clone)]
+pub struct BirdSkeleton {
+    head: Bone,
+    torso: Bone,
+    tail: Bone,
+    l_wing: Bone,
+    r_wing: Bone,
+    l_foot: Bone,
+    r_foot: Bone,
+}
+
+impl BirdSkeleton {
+    pub fn new() -> Self {
+        Self {
+            head: Bone::default(),
+            torso: Bone::default(),
+            tail: Bone::default(),
+            l_wing: Bone::default(),
+            r_wing: Bone::default(),
+            l_foot: Bone::default(),
+            r_foot: Bone::default(),
+        }
+    }
+}
+
+impl Skeleton for BirdSkeleton {
+    fn compute_matrices(&self) -> [FigureBoneData; 16] {
+        [
+            FigureBoneData::new(self.head.compute_base_matrix()),
+            FigureBoneData::new(self.torso.compute_base_matrix()),
+            FigureBoneData::new(self.tail.compute_base_matrix()),
+            FigureBoneData::new(self.l_wing.compute_base_matrix()),
+            FigureBoneData::new(self.r_wing.compute_base_matrix()),
+            FigureBoneData::new(self.l_foot.compute_base_matrix()),
+            FigureBoneData::new(self.r_foot.compute_base_matrix()),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+        ]
+    }
+
+    fn interpolate(&mut self, target: &Self) {
+        self.head.interpolate(&target.head);
+        self.torso.interpolate(&target.torso);
+        self.tail.interpolate(&target.tail);
+        self.l_wing.interpolate(&target.l_wing);
+        self.r_wing.interpolate(&target.r_wing);
+        self.l_foot.interpolate(&target.l_foot);
+        self.r_foot.interpolate(&target.r_foot);
+    }
+}
@@ -0,0 +1,49 @@
+// Library
+use vek::*;
+
+// Local
+use super::{
+    BirdSkeleton,
+    super::Animation,
+    SCALE,
+};
+
+pub struct RunAnimation;
+
+impl Animation for RunAnimation {
+    type Skeleton = BirdSkeleton;
+    type Dependency = f64;
+
+    fn update_skeleton(
+        skeleton: &Self::Skeleton,
+        time: f64,
+    ) -> Self::Skeleton {
+        let mut next = (*skeleton).clone();
+
+        let wave = (time as f32 * 16.0).sin();
+        let wavecos = (time as f32 * 16.0).cos();
+        let flap = (time as f32 * 24.0).sin();
+
+        next.head.offset = Vec3::new(3.5 + wavecos * 0.5, 0.0, 9.0) / SCALE;
+        next.head.ori = Quaternion::rotation_y(0.2);
+
+        next.torso.offset = Vec3::new(0.0, 0.0, 5.0 + wave.abs() * 0.5) / SCALE;
+        next.torso.ori = Quaternion::rotation_y(0.1);
+
+        next.tail.offset = Vec3::new(-4.0, 0.0, 6.0) / SCALE;
+        next.tail.ori = Quaternion::rotation_y(-0.1 + wave * 0.1);
+
+        // Flap for balance while running
+        next.l_wing.offset = Vec3::new(0.0, 3.0, 6.0) / SCALE;
+        next.l_wing.ori = Quaternion::rotation_x(-0.5 + flap * 0.6);
+        next.r_wing.offset = Vec3::new(0.0, -3.0, 6.0) / SCALE;
+        next.r_wing.ori = Quaternion::rotation_x(0.5 - flap * 0.6);
+
+        next.l_foot.offset = Vec3::new(wavecos * 1.5, 1.5, 3.0 + wave.max(0.0)) / SCALE;
+        next.l_foot.ori = Quaternion::rotation_y(wave * -0.6);
+        next.r_foot.offset = Vec3::new(-wavecos * 1.5, -1.5, 3.0 + (-wave).max(0.0)) / SCALE;
+        next.r_foot.ori = Quaternion::rotation_y(wave * 0.6);
+
+        next
+    }
+}
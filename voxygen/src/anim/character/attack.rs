@@ -0,0 +1,56 @@
+// Library
+use vek::*;
+
+// Local
+use super::{
+    CharacterSkeleton,
+    super::Animation,
+    SCALE,
+};
+
+pub struct AttackAnimation;
+
+impl Animation for AttackAnimation {
+    type Skeleton = CharacterSkeleton;
+    type Dependency = f64;
+
+    fn update_skeleton(
+        skeleton: &Self::Skeleton,
+        time: f64,
+    ) -> Self::Skeleton {
+        let mut next = (*skeleton).clone();
+
+        // One full swing every attack (0.5 seconds)
+        let swing = (time as f32 * 12.57).sin();
+        let swingcos = (time as f32 * 12.57).cos();
+
+        next.head.offset = Vec3::new(0.0, 0.0, 12.0) / SCALE;
+        next.head.ori = Quaternion::rotation_z(swing * 0.2);
+
+        next.chest.offset = Vec3::new(0.0, 0.0, 8.0) / SCALE;
+        next.chest.ori = Quaternion::rotation_z(swing * 0.4);
+
+        next.belt.offset = Vec3::new(0.0, 0.0, 6.0) / SCALE;
+        next.belt.ori = Quaternion::rotation_z(swing * 0.25);
+
+        next.shorts.offset = Vec3::new(0.0, 0.0, 3.0) / SCALE;
+        next.shorts.ori = Quaternion::rotation_z(swing * 0.1);
+
+        // The weapon hand sweeps across the body
+        next.l_hand.offset = Vec3::new(4.0 + swingcos * 3.0, 7.5 - swing * 4.0, 12.0) / SCALE;
+        next.l_hand.ori = Quaternion::rotation_x(swing * 0.6) * Quaternion::rotation_y(-1.2);
+        next.r_hand.offset = Vec3::new(0.0, -7.5, 11.0) / SCALE;
+        next.r_hand.ori = Quaternion::rotation_y(0.0);
+
+        next.l_foot.offset = Vec3::new(3.5, 3.4, 6.0) / SCALE;
+        next.l_foot.ori = Quaternion::rotation_y(-0.2);
+        next.r_foot.offset = Vec3::new(-1.5, -3.4, 6.0) / SCALE;
+        next.r_foot.ori = Quaternion::rotation_y(0.2);
+
+        next.back.offset = Vec3::new(-6.5, 16.0, 15.0);
+        next.back.ori = Quaternion::rotation_x(2.5);
+        next.back.scale = Vec3::one();
+
+        next
+    }
+}
@@ -0,0 +1,52 @@
+// Library
+use vek::*;
+
+// Local
+use super::{
+    CharacterSkeleton,
+    super::Animation,
+    SCALE,
+};
+
+pub struct DeathAnimation;
+
+impl Animation for DeathAnimation {
+    type Skeleton = CharacterSkeleton;
+    type Dependency = f64;
+
+    fn update_skeleton(
+        skeleton: &Self::Skeleton,
+        _time: f64,
+    ) -> Self::Skeleton {
+        let mut next = (*skeleton).clone();
+
+        // Lying flat on the ground, face up
+        next.head.offset = Vec3::new(-12.0, 0.0, 1.5) / SCALE;
+        next.head.ori = Quaternion::rotation_y(-1.5);
+
+        next.chest.offset = Vec3::new(-8.0, 0.0, 1.5) / SCALE;
+        next.chest.ori = Quaternion::rotation_y(-1.5);
+
+        next.belt.offset = Vec3::new(-6.0, 0.0, 1.5) / SCALE;
+        next.belt.ori = Quaternion::rotation_y(-1.5);
+
+        next.shorts.offset = Vec3::new(-3.0, 0.0, 1.5) / SCALE;
+        next.shorts.ori = Quaternion::rotation_y(-1.5);
+
+        next.l_hand.offset = Vec3::new(-10.0, 8.5, 1.0) / SCALE;
+        next.l_hand.ori = Quaternion::rotation_y(-1.5);
+        next.r_hand.offset = Vec3::new(-10.0, -8.5, 1.0) / SCALE;
+        next.r_hand.ori = Quaternion::rotation_y(-1.5);
+
+        next.l_foot.offset = Vec3::new(1.0, 3.4, 1.0) / SCALE;
+        next.l_foot.ori = Quaternion::rotation_y(-1.5);
+        next.r_foot.offset = Vec3::new(1.0, -3.4, 1.0) / SCALE;
+        next.r_foot.ori = Quaternion::rotation_y(-1.5);
+
+        next.back.offset = Vec3::new(-6.5, 16.0, 15.0);
+        next.back.ori = Quaternion::rotation_x(2.5);
+        next.back.scale = Vec3::one();
+
+        next
+    }
+}
@@ -0,0 +1,56 @@
+// Library
+use vek::*;
+
+// Local
+use super::{
+    CharacterSkeleton,
+    super::Animation,
+    SCALE,
+};
+
+pub struct GlideAnimation;
+
+impl Animation for GlideAnimation {
+    type Skeleton = CharacterSkeleton;
+    type Dependency = f64;
+
+    fn update_skeleton(
+        skeleton: &Self::Skeleton,
+        time: f64,
+    ) -> Self::Skeleton {
+        let mut next = (*skeleton).clone();
+
+        let wave_slow = (time as f32 * 2.0).sin();
+        let wave_slow_cos = (time as f32 * 2.0).cos();
+
+        next.head.offset = Vec3::new(0.0, 0.0, 12.0) / SCALE;
+        next.head.ori = Quaternion::rotation_y(-0.3);
+
+        next.chest.offset = Vec3::new(0.0, 0.0, 8.0) / SCALE;
+        next.chest.ori = Quaternion::rotation_y(0.0);
+
+        next.belt.offset = Vec3::new(0.0, 0.0, 6.0) / SCALE;
+        next.belt.ori = Quaternion::rotation_y(0.0);
+
+        next.shorts.offset = Vec3::new(0.0, 0.0, 3.0) / SCALE;
+        next.shorts.ori = Quaternion::rotation_y(0.0);
+
+        // Arms held out wide, swaying gently in the wind
+        next.l_hand.offset = Vec3::new(0.0, 12.0, 14.0 + wave_slow * 0.5) / SCALE;
+        next.l_hand.ori = Quaternion::rotation_x(1.4 + wave_slow * 0.05);
+        next.r_hand.offset = Vec3::new(0.0, -12.0, 14.0 - wave_slow * 0.5) / SCALE;
+        next.r_hand.ori = Quaternion::rotation_x(-1.4 + wave_slow * 0.05);
+
+        // Legs trailing behind
+        next.l_foot.offset = Vec3::new(-2.0, 3.4, 6.0 + wave_slow_cos * 0.3) / SCALE;
+        next.l_foot.ori = Quaternion::rotation_y(0.6);
+        next.r_foot.offset = Vec3::new(-2.0, -3.4, 6.0 - wave_slow_cos * 0.3) / SCALE;
+        next.r_foot.ori = Quaternion::rotation_y(0.6);
+
+        next.back.offset = Vec3::new(-6.5, 16.0, 15.0);
+        next.back.ori = Quaternion::rotation_x(2.5);
+        next.back.scale = Vec3::one();
+
+        next
+    }
+}
@@ -0,0 +1,55 @@
+// Library
+use vek::*;
+
+// Local
+use super::{
+    CharacterSkeleton,
+    super::Animation,
+    SCALE,
+};
+
+pub struct JumpAnimation;
+
+impl Animation for JumpAnimation {
+    type Skeleton = CharacterSkeleton;
+    type Dependency = f64;
+
+    fn update_skeleton(
+        skeleton: &Self::Skeleton,
+        time: f64,
+    ) -> Self::Skeleton {
+        let mut next = (*skeleton).clone();
+
+        let wave_slow = (time as f32 * 4.0).sin();
+
+        next.head.offset = Vec3::new(0.0, 0.0, 12.0) / SCALE;
+        next.head.ori = Quaternion::rotation_y(0.0);
+
+        next.chest.offset = Vec3::new(0.0, 0.0, 8.0) / SCALE;
+        next.chest.ori = Quaternion::rotation_y(0.0);
+
+        next.belt.offset = Vec3::new(0.0, 0.0, 6.0) / SCALE;
+        next.belt.ori = Quaternion::rotation_y(0.0);
+
+        next.shorts.offset = Vec3::new(0.0, 0.0, 3.0) / SCALE;
+        next.shorts.ori = Quaternion::rotation_y(0.0);
+
+        // Arms raised for balance
+        next.l_hand.offset = Vec3::new(0.0, 8.5, 13.0 + wave_slow * 0.5) / SCALE;
+        next.l_hand.ori = Quaternion::rotation_x(0.6);
+        next.r_hand.offset = Vec3::new(0.0, -8.5, 13.0 + wave_slow * 0.5) / SCALE;
+        next.r_hand.ori = Quaternion::rotation_x(-0.6);
+
+        // One leg tucked up, the other trailing
+        next.l_foot.offset = Vec3::new(3.5, 3.4, 7.0) / SCALE;
+        next.l_foot.ori = Quaternion::rotation_y(-0.6);
+        next.r_foot.offset = Vec3::new(-1.5, -3.4, 6.0) / SCALE;
+        next.r_foot.ori = Quaternion::rotation_y(0.4);
+
+        next.back.offset = Vec3::new(-6.5, 16.0, 15.0);
+        next.back.ori = Quaternion::rotation_x(2.5);
+        next.back.scale = Vec3::one();
+
+        next
+    }
+}
@@ -1,9 +1,17 @@
 pub mod run;
 pub mod idle;
+pub mod jump;
+pub mod glide;
+pub mod death;
+pub mod attack;
 
 // Reexports
 pub use self::run::RunAnimation;
 pub use self::idle::IdleAnimation;
+pub use self::jump::JumpAnimation;
+pub use self::glide::GlideAnimation;
+pub use self::death::DeathAnimation;
+pub use self::attack::AttackAnimation;
 
 // Crate
 use crate::render::FigureBoneData;
@@ -1,4 +1,6 @@
+pub mod bird;
 pub mod character;
+pub mod quadruped;
 
 // Library
 use vek::*;
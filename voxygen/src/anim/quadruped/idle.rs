@@ -0,0 +1,47 @@
+// Library
+use vek::*;
+
+// Local
+use super::{
+    QuadrupedSkeleton,
+    super::Animation,
+    SCALE,
+    TORSO_HEIGHT,
+};
+
+pub struct IdleAnimation;
+
+impl Animation for IdleAnimation {
+    type Skeleton = QuadrupedSkeleton;
+    type Dependency = f64;
+
+    fn update_skeleton(
+        skeleton: &Self::Skeleton,
+        time: f64,
+    ) -> Self::Skeleton {
+        let mut next = (*skeleton).clone();
+
+        let breathe = (time as f32 * 2.0).sin();
+        let sniff = (time as f32 * 0.7).sin();
+
+        next.head.offset = Vec3::new(9.0, 0.0, 11.0 + breathe * 0.3) / SCALE;
+        next.head.ori = Quaternion::rotation_z(sniff * 0.3) * Quaternion::rotation_y(0.1 + breathe * 0.05);
+
+        next.torso.offset = Vec3::new(0.0, 0.0, TORSO_HEIGHT + breathe * 0.2) / SCALE;
+        next.torso.ori = Quaternion::rotation_y(0.0);
+
+        next.tail.offset = Vec3::new(-7.0, 0.0, 11.0) / SCALE;
+        next.tail.ori = Quaternion::rotation_z(sniff * 0.4) * Quaternion::rotation_y(-0.4);
+
+        next.lf_leg.offset = Vec3::new(5.0, 3.0, 6.0) / SCALE;
+        next.lf_leg.ori = Quaternion::rotation_y(0.0);
+        next.rf_leg.offset = Vec3::new(5.0, -3.0, 6.0) / SCALE;
+        next.rf_leg.ori = Quaternion::rotation_y(0.0);
+        next.lb_leg.offset = Vec3::new(-5.0, 3.0, 6.0) / SCALE;
+        next.lb_leg.ori = Quaternion::rotation_y(0.0);
+        next.rb_leg.offset = Vec3::new(-5.0, -3.0, 6.0) / SCALE;
+        next.rb_leg.ori = Quaternion::rotation_y(0.0);
+
+        next
+    }
+}
@@ -0,0 +1,77 @@
+pub mod run;
+pub mod idle;
+
+// Reexports
+pub use self::run::RunAnimation;
+pub use self::idle::IdleAnimation;
+
+// Project
+// The skeleton's proportions are shared with the server, which seats riders on top of the torso
+pub use common::comp::body::quadruped::{SCALE, TORSO_HEIGHT};
+
+// Crate
+use crate::render::FigureBoneData;
+
+// Local
+use super::{
+    Skeleton,
+    Bone,
+};
+
+#[derive(Clone)]
+pub struct QuadrupedSkeleton {
+    head: Bone,
+    torso: Bone,
+    tail: Bone,
+    lf_leg: Bone,
+    rf_leg: Bone,
+    lb_leg: Bone,
+    rb_leg: Bone,
+}
+
+impl QuadrupedSkeleton {
+    pub fn new() -> Self {
+        Self {
+            head: Bone::default(),
+            torso: Bone::default(),
+            tail: Bone::default(),
+            lf_leg: Bone::default(),
+            rf_leg: Bone::default(),
+            lb_leg: Bone::default(),
+            rb_leg: Bone::default(),
+        }
+    }
+}
+
+impl Skeleton for QuadrupedSkeleton {
+    fn compute_matrices(&self) -> [FigureBoneData; 16] {
+        [
+            FigureBoneData::new(self.head.compute_base_matrix()),
+            FigureBoneData::new(self.torso.compute_base_matrix()),
+            FigureBoneData::new(self.tail.compute_base_matrix()),
+            FigureBoneData::new(self.lf_leg.compute_base_matrix()),
+            FigureBoneData::new(self.rf_leg.compute_base_matrix()),
+            FigureBoneData::new(self.lb_leg.compute_base_matrix()),
+            FigureBoneData::new(self.rb_leg.compute_base_matrix()),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+            FigureBoneData::default(),
+        ]
+    }
+
+    fn interpolate(&mut self, target: &Self) {
+        self.head.interpolate(&target.head);
+        self.torso.interpolate(&target.torso);
+        self.tail.interpolate(&target.tail);
+        self.lf_leg.interpolate(&target.lf_leg);
+        self.rf_leg.interpolate(&target.rf_leg);
+        self.lb_leg.interpolate(&target.lb_leg);
+        self.rb_leg.interpolate(&target.rb_leg);
+    }
+}
@@ -0,0 +1,49 @@
+// Library
+use vek::*;
+
+// Local
+use super::{
+    QuadrupedSkeleton,
+    super::Animation,
+    SCALE,
+    TORSO_HEIGHT,
+};
+
+pub struct RunAnimation;
+
+impl Animation for RunAnimation {
+    type Skeleton = QuadrupedSkeleton;
+    type Dependency = f64;
+
+    fn update_skeleton(
+        skeleton: &Self::Skeleton,
+        time: f64,
+    ) -> Self::Skeleton {
+        let mut next = (*skeleton).clone();
+
+        let wave = (time as f32 * 14.0).sin();
+        let wavecos = (time as f32 * 14.0).cos();
+        let bounce = (time as f32 * 28.0).sin();
+
+        next.head.offset = Vec3::new(9.5, 0.0, 11.0 + bounce * 0.6) / SCALE;
+        next.head.ori = Quaternion::rotation_y(0.2 + wave * 0.1);
+
+        next.torso.offset = Vec3::new(0.0, 0.0, TORSO_HEIGHT + bounce * 0.6) / SCALE;
+        next.torso.ori = Quaternion::rotation_y(wave * 0.08);
+
+        next.tail.offset = Vec3::new(-7.0, 0.0, 11.0 + bounce * 0.6) / SCALE;
+        next.tail.ori = Quaternion::rotation_y(-0.8 + wavecos * 0.2);
+
+        // Diagonally opposite legs move together
+        next.lf_leg.offset = Vec3::new(5.0 + wavecos * 1.5, 3.0, 6.0 + wave.max(0.0) * 1.5) / SCALE;
+        next.lf_leg.ori = Quaternion::rotation_y(wave * -0.8);
+        next.rb_leg.offset = Vec3::new(-5.0 + wavecos * 1.5, -3.0, 6.0 + wave.max(0.0) * 1.5) / SCALE;
+        next.rb_leg.ori = Quaternion::rotation_y(wave * -0.8);
+        next.rf_leg.offset = Vec3::new(5.0 - wavecos * 1.5, -3.0, 6.0 + (-wave).max(0.0) * 1.5) / SCALE;
+        next.rf_leg.ori = Quaternion::rotation_y(wave * 0.8);
+        next.lb_leg.offset = Vec3::new(-5.0 - wavecos * 1.5, 3.0, 6.0 + (-wave).max(0.0) * 1.5) / SCALE;
+        next.lb_leg.ori = Quaternion::rotation_y(wave * 0.8);
+
+        next
+    }
+}
@@ -17,10 +17,6 @@ use conrod_core::{
 
 widget_ids! {
     struct Ids {
-        // Test
-        bag_space_add,
-        inventorytest_button,
-        inventorytest_button_label,
         // Logo
         v_logo,
 
@@ -32,7 +28,6 @@ widget_ids! {
         inv_alignment,
         inv_grid,
         inv_scrollbar,
-        inv_slot_0,
         inv_slot[],
         //help
         help,
@@ -382,6 +377,8 @@ enum SettingsTab {
 
 pub enum Event {
     SendMessage(String),
+    /// Move the contents of one inventory slot to another
+    MoveInventorySlot { from: usize, to: usize },
     Logout,
     Quit,
 }
@@ -415,11 +412,13 @@ pub struct Hud {
     open_windows: Windows,
     map_open: bool,
     show_ui: bool,
-    inventory_space: u32,
+    // The name and amount of the item stack in each inventory slot
+    inventory: Vec<Option<(String, u32)>>,
+    // The slot clicked first when moving items between slots
+    selected_slot: Option<usize>,
     xp_percentage: f64,
     hp_percentage: f64,
     mana_percentage: f64,
-    inventorytest_button: bool,
     settings_tab: SettingsTab,
 }
 
@@ -461,8 +460,8 @@ impl Hud {
             menu_open: false,
             map_open: false,
             show_ui: true,
-            inventorytest_button: false,
-            inventory_space: 0,
+            inventory: Vec::new(),
+            selected_slot: None,
             open_windows: Windows::None,
             font_metamorph,
             font_opensans,
@@ -481,27 +480,13 @@ impl Hud {
         const HP_COLOR: Color = Color::Rgba(0.33, 0.63, 0.0, 1.0);
         const MANA_COLOR: Color = Color::Rgba(0.42, 0.41, 0.66, 1.0);
         const XP_COLOR: Color = Color::Rgba(0.59, 0.41, 0.67, 1.0);
+        const SELECTED_SLOT_COLOR: Color = Color::Rgba(1.0, 0.85, 0.3, 1.0);
 
         // Don't show anything if the ui is toggled off
         if !self.show_ui {
             return events;
         }
     
-        // Add Bag-Space Button
-        if self.inventorytest_button {
-            if Button::image(self.imgs.mmap_button)
-                .w_h(100.0, 100.0)
-                .middle_of(ui_widgets.window)
-                .label("1 Up!")
-                .label_font_size(20)
-                .hover_image(self.imgs.mmap_button_hover)
-                .press_image(self.imgs.mmap_button_press)
-                .set(self.ids.bag_space_add, ui_widgets)
-                .was_clicked()
-            {
-                self.inventory_space = self.inventory_space + 1;
-            };
-        }
         // Chat box
         if let Some(msg) = self
             .chat
@@ -830,32 +815,46 @@ impl Hud {
                 self.bag_open = false;
             }
 
-            if self.inventory_space > 0 {
-                // First Slot
-                Button::image(self.imgs.inv_slot)
-                    .top_left_with_margins_on(self.ids.inv_grid, 5.0, 5.0)
+            // Slots
+            if self.ids.inv_slot.len() < self.inventory.len() {
+                self.ids.inv_slot.resize(self.inventory.len(), &mut ui_widgets.widget_id_generator());
+            }
+            for (i, slot) in self.inventory.iter().enumerate() {
+                let label = match slot {
+                    Some((name, amount)) if *amount > 1 => format!("{}\n{}", name, amount),
+                    Some((name, _)) => name.clone(),
+                    None => String::new(),
+                };
+                // The selected slot is highlighted until the stack is moved
+                let label_color = if self.selected_slot == Some(i) {
+                    SELECTED_SLOT_COLOR
+                } else {
+                    TEXT_COLOR
+                };
+                if Button::image(self.imgs.inv_slot)
+                    .top_left_with_margins_on(
+                        self.ids.inv_grid,
+                        5.0 + (i / 5) as f64 * 45.0,
+                        5.0 + (i % 5) as f64 * 45.0,
+                    )
                     .w_h(40.0, 40.0)
-                    .set(self.ids.inv_slot_0, ui_widgets);
+                    .label(&label)
+                    .label_color(label_color)
+                    .label_font_size(8)
+                    .set(self.ids.inv_slot[i], ui_widgets)
+                    .was_clicked()
+                {
+                    // Click a stack, then the slot it should be moved to
+                    self.selected_slot = match self.selected_slot {
+                        Some(from) => {
+                            events.push(Event::MoveInventorySlot { from, to: i });
+                            None
+                        }
+                        None if slot.is_some() => Some(i),
+                        None => None,
+                    };
+                }
             }
-            // if self.ids.inv_slot.len() < self.inventory_space {
-            //    self.ids.inv_slot.resize(self.inventory_space, &mut ui_widgets.widget_id_generator());
-            //}
-
-            //let num = self.ids.inv_slot.len();
-            //println!("self.ids.inv_slot.len(): {:?}", num);
-            //if num > 0 {
-            //Button::image(self.imgs.inv_slot)
-            //.top_left_with_margins_on(self.ids.inv_grid, 5.0, 5.0)
-            //.w_h(40.0, 40.0)
-            //.set(self.ids.inv_slot[0], ui_widgets);
-            //}
-            //for i in 1..5 {
-            //Button::image(self.imgs.inv_slot)
-            //.right(10.0)
-            //.label(&format!("{}", i + 1))
-            //.label_color(TEXT_COLOR)
-            //.label_font_size(5)
-            //.set(self.ids.inv_slot[i], ui_widgets);}
         }
 
         // Bag
@@ -950,24 +949,6 @@ impl Hud {
                     .graphics_for(self.ids.button_help)
                     .color(TEXT_COLOR)
                     .set(self.ids.show_help_label, ui_widgets);
-
-                self.inventorytest_button = ToggleButton::new(
-                    self.inventorytest_button,
-                    self.imgs.check,
-                    self.imgs.check_checked,
-                )
-                .w_h(288.0 / 24.0, 288.0 / 24.0)
-                .top_left_with_margins_on(self.ids.rectangle, 40.0, 15.0)
-                .hover_images(self.imgs.check_checked_mo, self.imgs.check_mo)
-                .press_images(self.imgs.check_press, self.imgs.check_press)
-                .set(self.ids.inventorytest_button, ui_widgets);
-                Text::new("Show Inventory Test Button")
-                    .right_from(self.ids.inventorytest_button, 10.0)
-                    .font_size(12)
-                    .font_id(self.font_opensans)
-                    .graphics_for(self.ids.inventorytest_button)
-                    .color(TEXT_COLOR)
-                    .set(self.ids.inventorytest_button_label, ui_widgets);
             }
             //2 Gameplay////////////////
             if Button::image(if let SettingsTab::Gameplay = self.settings_tab {
@@ -1455,6 +1436,20 @@ impl Hud {
         self.chat.new_message(msg);
     }
 
+    /// Set how full the health bar is, between 0 and 1.
+    pub fn set_health(&mut self, fraction: f32) {
+        self.hp_percentage = fraction.max(0.0).min(1.0) as f64;
+    }
+
+    /// Set the contents of the inventory shown in the bag, as the name and amount of the item
+    /// stack in each slot.
+    pub fn set_inventory(&mut self, inventory: Vec<Option<(String, u32)>>) {
+        if self.selected_slot.map(|slot| slot >= inventory.len()).unwrap_or(false) {
+            self.selected_slot = None;
+        }
+        self.inventory = inventory;
+    }
+
     fn toggle_menu(&mut self) {
         self.menu_open = !self.menu_open;
     }
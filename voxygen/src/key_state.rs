@@ -1,4 +1,5 @@
 use vek::Vec2;
+use common::comp::Gait;
 
 pub struct KeyState {
     pub right: bool,
@@ -6,6 +7,10 @@ pub struct KeyState {
     pub up: bool,
     pub down: bool,
     pub jump: bool,
+    pub glide: bool,
+    pub sprint: bool,
+    pub walk: bool,
+    pub attack: bool,
 }
 
 impl KeyState {
@@ -16,6 +21,10 @@ impl KeyState {
             up: false,
             down: false,
             jump: false,
+            glide: false,
+            sprint: false,
+            walk: false,
+            attack: false,
         }
     }
 
@@ -29,4 +38,22 @@ impl KeyState {
     pub fn jump(&self) -> bool {
         self.jump
     }
+
+    pub fn glide(&self) -> bool {
+        self.glide
+    }
+
+    pub fn attack(&self) -> bool {
+        self.attack
+    }
+
+    pub fn gait(&self) -> Gait {
+        if self.walk {
+            Gait::Walk
+        } else if self.sprint {
+            Gait::Sprint
+        } else {
+            Gait::Run
+        }
+    }
 }
@@ -21,6 +21,8 @@ pub struct CharSelectionState {
 impl CharSelectionState {
     /// Create a new `CharSelectionState`
     pub fn new(window: &mut Window, client: Rc<RefCell<Client>>) -> Self {
+        // The character list is only sent on request
+        client.borrow_mut().request_character_list();
         Self {
             char_selection_ui: CharSelectionUi::new(window),
             client,
@@ -61,25 +63,47 @@ impl PlayState for CharSelectionState {
             global_state.window.renderer_mut().clear(BG_COLOR);
 
             // Maintain the UI
+            if let Some(characters) = self.client.borrow().character_list() {
+                self.char_selection_ui.set_characters(characters);
+            }
             for event in self.char_selection_ui.maintain(global_state.window.renderer_mut()) {
                 match event {
                     ui::Event::Logout => {
                         global_state.singleplayer = None;
                         return PlayStateResult::Pop;
                     },
-                    ui::Event::Play => return PlayStateResult::Switch(
-                        Box::new(SessionState::new(&mut global_state.window, self.client.clone()))
-                    ),
+                    // Only enter the world once the server confirms the character was selected
+                    ui::Event::Play(name) => {
+                        self.char_selection_ui.set_error(None);
+                        self.client.borrow_mut().select_character(name);
+                    },
+                    ui::Event::Create { name, character } => {
+                        self.char_selection_ui.set_error(None);
+                        self.client.borrow_mut().create_character(name, character);
+                    },
+                    ui::Event::Delete(name) => {
+                        self.char_selection_ui.set_error(None);
+                        self.client.borrow_mut().delete_character(name);
+                    },
                 }
             }
 
             // Draw the UI to the screen
             self.char_selection_ui.render(global_state.window.renderer_mut());
 
-            // Tick the client (currently only to keep the connection alive)
-            self.client.borrow_mut().tick(client::Input::default(), clock.get_last_delta())
+            // Tick the client to keep the connection alive and hear back about the characters
+            let events = self.client.borrow_mut().tick(client::Input::default(), clock.get_last_delta())
                 .expect("Failed to tick the client");
             self.client.borrow_mut().cleanup();
+            for event in events {
+                match event {
+                    client::Event::CharacterActionError(err) => self.char_selection_ui.set_error(Some(err)),
+                    client::Event::CharacterSelected => return PlayStateResult::Switch(
+                        Box::new(SessionState::new(&mut global_state.window, self.client.clone()))
+                    ),
+                    _ => {},
+                }
+            }
 
             // Finish the frame
             global_state.window.renderer_mut().flush();
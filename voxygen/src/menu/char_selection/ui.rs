@@ -3,18 +3,30 @@ use crate::{
     ui::{self, ScaleMode, Ui},
     window::Window,
 };
-use common::assets;
+use common::{
+    assets,
+    comp::{
+        self,
+        character::{Gender, Race, Weapon, HAIR_STYLE_COUNT, HAIR_COLORS, SKIN_COLORS, EYE_COLORS},
+    },
+    msg::CharacterInfo,
+};
 use conrod_core::{
     color,
     color::TRANSPARENT,
     image::Id as ImgId,
     text::font::Id as FontId,
-    widget::{text_box::Event as TextBoxEvent, Button, Image, Rectangle, Text, TextBox},
-    widget_ids, Borderable, Color, Colorable, Labelable, Positionable, Sizeable, Widget,
+    widget::{self, text_box::Event as TextBoxEvent, Button, Image, Rectangle, Text, TextBox},
+    widget_ids, Borderable, Color, Colorable, Labelable, Positionable, Sizeable, UiCell, Widget,
 };
+use vek::Rgb;
 
 widget_ids! {
     struct Ids {
+        // Character list
+        character_buttons[],
+        error_text,
+
         // Background and logo
         bg_selection,
         bg_creation,
@@ -76,7 +88,6 @@ widget_ids! {
         weapon_7,
 
         //test_chars
-        test_char_l_button,
         test_char_l_big,
         help_text_bg,
         help_text,
@@ -116,6 +127,9 @@ widget_ids! {
         window_acessories_mid,
         window_acessories_bot,
         skin_color_picker,
+        skin_color_swatches[],
+        eye_color_swatches[],
+        hair_color_swatches[],
         skin_color_slider,
         skin_color_text,
         skin_color_slider_text,
@@ -166,7 +180,6 @@ struct Imgs {
     button_dark_red_hover: ImgId,
     button_dark_red_press: ImgId,
     selection_window: ImgId,
-    test_char_l_button: ImgId,
     test_char_l_big: ImgId,
     name_input: ImgId,
     creation_window: ImgId,
@@ -248,7 +261,6 @@ impl Imgs {
             button_dark_red: load("element/buttons/button_dark_red.png"),
             button_dark_red_hover: load("element/buttons/button_dark_red_hover.png"),
             button_dark_red_press: load("element/buttons/button_dark_red_press.png"),
-            test_char_l_button: load("element/misc_backgrounds/test_char_l.png"),
             test_char_l_big: load("element/misc_backgrounds/test_char_l_big.png"),
             name_input: load("element/misc_backgrounds/textbox.png"),
             creation_window: load("element/frames/char_creation.png"),
@@ -315,6 +327,7 @@ enum CreationState {
     Weapon,
     Body(BodyPart),
 }
+#[derive(Clone, Copy)]
 enum Races {
     Human,
     Orc,
@@ -329,11 +342,13 @@ enum BodyPart {
     Hair,
     Accessories,
 }
+#[derive(Clone, Copy)]
 enum Sex {
     Male,
     Female,
     Undefined,
 }
+#[derive(Clone, Copy)]
 enum Weapons {
     Daggers,
     SwordShield,
@@ -344,13 +359,87 @@ enum Weapons {
     Staff,
 }
 
+impl From<Races> for Race {
+    fn from(race: Races) -> Self {
+        match race {
+            Races::Human => Race::Human,
+            Races::Orc => Race::Orc,
+            Races::Elf => Race::Elf,
+            Races::Dwarf => Race::Dwarf,
+            Races::Undead => Race::Undead,
+            Races::Danari => Race::Danari,
+        }
+    }
+}
+
+impl From<Sex> for Gender {
+    fn from(sex: Sex) -> Self {
+        match sex {
+            Sex::Male => Gender::Male,
+            Sex::Female => Gender::Female,
+            Sex::Undefined => Gender::Unspecified,
+        }
+    }
+}
+
+impl From<Weapons> for Weapon {
+    fn from(weapon: Weapons) -> Self {
+        match weapon {
+            Weapons::Daggers => Weapon::Daggers,
+            Weapons::SwordShield => Weapon::SwordShield,
+            Weapons::Sword => Weapon::Sword,
+            Weapons::Axe => Weapon::Axe,
+            Weapons::Hammer => Weapon::Hammer,
+            Weapons::Bow => Weapon::Bow,
+            Weapons::Staff => Weapon::Staff,
+        }
+    }
+}
+
 pub enum Event {
     Logout,
-    Play,
+    /// Enter the world with the character of the given name
+    Play(String),
+    Create {
+        name: String,
+        character: comp::Character,
+    },
+    Delete(String),
 }
 
 const TEXT_COLOR: Color = Color::Rgba(1.0, 1.0, 1.0, 1.0);
 const TEXT_BG: Color = Color::Rgba(0.0, 0.0, 0.0, 1.0);
+const ERROR_COLOR: Color = Color::Rgba(1.0, 0.4, 0.4, 1.0);
+
+/// Draw a row of buttons inside `parent`, one for each colour in `palette`, with the `selected`
+/// one outlined. Returns the index of the colour that was clicked, if any.
+fn color_swatches(
+    ids: &mut widget::id::List,
+    palette: &[Rgb<u8>],
+    selected: u8,
+    parent: widget::Id,
+    ui_widgets: &mut UiCell,
+) -> Option<u8> {
+    if ids.len() < palette.len() {
+        ids.resize(palette.len(), &mut ui_widgets.widget_id_generator());
+    }
+    let mut clicked = None;
+    for (i, rgb) in palette.iter().enumerate() {
+        let rgb = rgb.map(|e| e as f32 / 255.0);
+        if Button::new()
+            .w_h(26.0, 26.0)
+            .top_left_with_margins_on(parent, 10.0, 10.0 + i as f64 * 29.0)
+            .color(Color::Rgba(rgb.r, rgb.g, rgb.b, 1.0))
+            .border(if i == selected as usize { 3.0 } else { 0.0 })
+            .border_color(TEXT_BG)
+            .set(ids[i], ui_widgets)
+            .was_clicked()
+        {
+            clicked = Some(i as u8);
+        }
+    }
+    clicked
+}
 
 pub struct CharSelectionUi {
     ui: Ui,
@@ -359,12 +448,21 @@ pub struct CharSelectionUi {
     font_metamorph: FontId,
     font_opensans: FontId,
     character_creation: bool,
-    selected_char_no: Option<i32>,
+    // The index of the selected character in the character list
+    selected_char_no: Option<usize>,
     race: Races,
     sex: Sex,
     weapon: Weapons,
+    hair_style: u8,
+    hair_color: u8,
+    skin_color: u8,
+    eye_color: u8,
     creation_state: CreationState,
     character_name: String,
+    // The account's characters, as reported by the server
+    characters: Vec<CharacterInfo>,
+    // The reason the server refused the last request, if it did
+    error: Option<String>,
 }
 
 impl CharSelectionUi {
@@ -399,7 +497,13 @@ impl CharSelectionUi {
             race: Races::Human,
             sex: Sex::Male,
             weapon: Weapons::Sword,
+            hair_style: 0,
+            hair_color: 0,
+            skin_color: 0,
+            eye_color: 0,
             creation_state: CreationState::Race,
+            characters: Vec::new(),
+            error: None,
         }
     }
 
@@ -456,16 +560,33 @@ impl CharSelectionUi {
                 self.character_creation = true;
                 self.selected_char_no = None;
             }
-            // Test Characters
-            if Button::image(self.imgs.test_char_l_button)
-                .bottom_left_with_margins_on(self.ids.bg_selection, 395.0, 716.0)
-                .w_h(95.0, 130.0)
-                .hover_image(self.imgs.test_char_l_button)
-                .press_image(self.imgs.test_char_l_button)
-                .set(self.ids.test_char_l_button, ui_widgets)
-                .was_clicked()
-            {
-                self.selected_char_no = Some(1);
+            // Character list
+            if self.ids.character_buttons.len() < self.characters.len() {
+                self.ids.character_buttons.resize(self.characters.len(), &mut ui_widgets.widget_id_generator());
+            }
+            for (i, info) in self.characters.iter().enumerate() {
+                if Button::image(self.imgs.button_dark)
+                    .top_left_with_margins_on(self.ids.bg_selection, 200.0 + i as f64 * 60.0, 40.0)
+                    .w_h(270.0, 50.0)
+                    .hover_image(self.imgs.button_dark_hover)
+                    .press_image(self.imgs.button_dark_press)
+                    .label(&info.name)
+                    .label_color(TEXT_COLOR)
+                    .label_font_size(20)
+                    .label_y(conrod_core::position::Relative::Scalar(3.0))
+                    .set(self.ids.character_buttons[i], ui_widgets)
+                    .was_clicked()
+                {
+                    self.selected_char_no = Some(i);
+                }
+            }
+
+            if let Some(error) = &self.error {
+                Text::new(error)
+                    .mid_top_with_margin_on(self.ids.bg_selection, 140.0)
+                    .font_size(20)
+                    .color(ERROR_COLOR)
+                    .set(self.ids.error_text, ui_widgets);
             }
 
             // Veloren Logo and Alpha Version
@@ -489,14 +610,15 @@ impl CharSelectionUi {
                 .color(TEXT_COLOR)
                 .set(self.ids.help_text, ui_widgets);
 
-            if let Some(no) = self.selected_char_no {
+            let characters = &self.characters;
+            if let Some(info) = self.selected_char_no.and_then(|no| characters.get(no)) {
                 // Selection_Window
                 Image::new(self.imgs.selection_window)
                     .w_h(522.0, 722.0)
                     .mid_right_with_margin_on(ui_widgets.window, 10.0)
                     .set(self.ids.selection_window, ui_widgets);
                 // Character Name & Level
-                Text::new("Character Name")
+                Text::new(&info.name)
                     .mid_top_with_margin_on(self.ids.selection_window, 80.0)
                     .font_size(30)
                     .color(TEXT_COLOR)
@@ -508,12 +630,10 @@ impl CharSelectionUi {
                     .set(self.ids.char_level, ui_widgets);
 
                 // Selected Character
-                if no == 1 {
-                    Image::new(self.imgs.test_char_l_big)
-                        .w_h(522.0, 722.0)
-                        .middle_of(self.ids.selection_window)
-                        .set(self.ids.test_char_l_big, ui_widgets);
-                }
+                Image::new(self.imgs.test_char_l_big)
+                    .w_h(522.0, 722.0)
+                    .middle_of(self.ids.selection_window)
+                    .set(self.ids.test_char_l_big, ui_widgets);
 
                 // Enter World Button
                 if Button::image(self.imgs.button_dark)
@@ -529,7 +649,7 @@ impl CharSelectionUi {
                     .was_clicked()
                 {
                     // Enter World
-                    events.push(Event::Play);
+                    events.push(Event::Play(info.name.clone()));
                 }
 
                 // Delete Button
@@ -544,7 +664,9 @@ impl CharSelectionUi {
                     .label_y(conrod_core::position::Relative::Scalar(3.0))
                     .set(self.ids.delete_button, ui_widgets)
                     .was_clicked()
-                {}
+                {
+                    events.push(Event::Delete(info.name.clone()));
+                }
             }
         }
         // Character_Creation //////////////
@@ -581,6 +703,16 @@ impl CharSelectionUi {
                 .set(self.ids.create_button, ui_widgets)
                 .was_clicked()
             {
+                events.push(Event::Create {
+                    name: self.character_name.clone(),
+                    character: comp::Character {
+                        hair_style: self.hair_style,
+                        hair_color: self.hair_color,
+                        skin_color: self.skin_color,
+                        eye_color: self.eye_color,
+                        ..comp::Character::new(self.race.into(), self.sex.into(), self.weapon.into())
+                    },
+                });
                 self.character_creation = false;
             }
             // Character Name Input
@@ -1306,14 +1438,19 @@ impl CharSelectionUi {
                             .font_size(25)
                             .color(TEXT_COLOR)
                             .set(self.ids.skin_color_text, ui_widgets);
-                        // TODO: Align Buttons here
-                        // They set an i32 to a value from 0-14
-                        // Depending on the race another color will be chosen
-                        // Here only the BG image changes depending on the race.
                         Rectangle::fill_with([192.0, 116.0], color::WHITE)
                             .top_right_with_margins_on(self.ids.skin_eyes_window, 60.0, 30.0)
                             .color(TEXT_COLOR)
                             .set(self.ids.skin_rect, ui_widgets);
+                        if let Some(skin_color) = color_swatches(
+                            &mut self.ids.skin_color_swatches,
+                            &SKIN_COLORS,
+                            self.skin_color,
+                            self.ids.skin_rect,
+                            ui_widgets,
+                        ) {
+                            self.skin_color = skin_color;
+                        }
 
                         // TODO:Slider
                         // Sliders actually change the Alpha-Level of the main colour chosen above
@@ -1340,15 +1477,19 @@ impl CharSelectionUi {
                             .font_size(25)
                             .color(TEXT_COLOR)
                             .set(self.ids.eye_color_text, ui_widgets);
-                        // TODO: Align 16 Buttons here
-                        //
-                        // They set a variable to a value from 0-14
-                        // Depending on the race another color will be chosen
-                        // Only the BG image (190x114 -> 2px border!) changes depending on the race.
                         Rectangle::fill_with([192.0, 116.0], color::WHITE)
                             .top_right_with_margins_on(self.ids.skin_eyes_window, 186.0, 30.0)
                             .color(TEXT_COLOR)
                             .set(self.ids.eyes_rect, ui_widgets);
+                        if let Some(eye_color) = color_swatches(
+                            &mut self.ids.eye_color_swatches,
+                            &EYE_COLORS,
+                            self.eye_color,
+                            self.ids.eyes_rect,
+                            ui_widgets,
+                        ) {
+                            self.eye_color = eye_color;
+                        }
 
                         // TODO:Slider
 
@@ -1389,7 +1530,9 @@ impl CharSelectionUi {
                             .right_from(self.ids.hair_style_text, 15.0)
                             .set(self.ids.hair_style_arrow_r, ui_widgets)
                             .was_clicked()
-                        {};
+                        {
+                            self.hair_style = (self.hair_style + 1) % HAIR_STYLE_COUNT;
+                        };
                         if Button::image(self.imgs.arrow_left)
                             .w_h(986.0 * 0.02, 1024.0 * 0.02)
                             .hover_image(self.imgs.arrow_left_mo)
@@ -1397,7 +1540,9 @@ impl CharSelectionUi {
                             .left_from(self.ids.hair_style_text, 15.0)
                             .set(self.ids.hair_style_arrow_l, ui_widgets)
                             .was_clicked()
-                        {};
+                        {
+                            self.hair_style = (self.hair_style + HAIR_STYLE_COUNT - 1) % HAIR_STYLE_COUNT;
+                        };
 
                         Text::new("Hair Color")
                             .top_left_with_margins_on(self.ids.hair_color_picker_bg, 0.0, -250.0)
@@ -1409,6 +1554,15 @@ impl CharSelectionUi {
                             .top_right_with_margins_on(self.ids.hair_window, 114.0, 30.0)
                             .color(TEXT_COLOR)
                             .set(self.ids.hair_color_picker_bg, ui_widgets);
+                        if let Some(hair_color) = color_swatches(
+                            &mut self.ids.hair_color_swatches,
+                            &HAIR_COLORS,
+                            self.hair_color,
+                            self.ids.hair_color_picker_bg,
+                            ui_widgets,
+                        ) {
+                            self.hair_color = hair_color;
+                        }
 
                         Image::new(self.imgs.slider_range)
                             .w_h(208.0, 12.0)
@@ -1889,6 +2043,21 @@ impl CharSelectionUi {
         events
     }
 
+    /// Set the characters belonging to the account.
+    pub fn set_characters(&mut self, characters: &[CharacterInfo]) {
+        // Keep the same character selected if the list changes
+        let selected = self.selected_char_no
+            .and_then(|no| self.characters.get(no))
+            .map(|info| info.name.clone());
+        self.characters = characters.to_vec();
+        self.selected_char_no = selected.and_then(|name| self.characters.iter().position(|info| info.name == name));
+    }
+
+    /// Show why the server refused the last request.
+    pub fn set_error(&mut self, error: Option<String>) {
+        self.error = error;
+    }
+
     pub fn handle_event(&mut self, event: ui::Event) {
         self.ui.handle_event(event);
     }
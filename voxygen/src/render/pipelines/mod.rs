@@ -12,6 +12,7 @@ use gfx::{
     gfx_impl_struct_meta,
 };
 use vek::*;
+use common::state::DAY_SECONDS;
 
 // Local
 use super::util::arr_to_mat;
@@ -26,6 +27,7 @@ gfx_defines! {
         view_distance: [f32; 4] = "view_distance",
         time_of_day: [f32; 4] = "time_of_day", // TODO: Make this f64
         tick: [f32; 4] = "tick",
+        sun_dir: [f32; 4] = "sun_dir",
     }
 }
 
@@ -40,6 +42,7 @@ impl Globals {
             view_distance: [0.0; 4],
             time_of_day: [0.0; 4],
             tick: [0.0; 4],
+            sun_dir: [0.0, 0.0, -1.0, 0.0],
         }
     }
 
@@ -59,8 +62,17 @@ impl Globals {
             cam_pos: Vec4::from(cam_pos).into_array(),
             focus_pos: Vec4::from(focus_pos).into_array(),
             view_distance: [view_distance; 4],
-            time_of_day: [time_of_day as f32; 4],
+            // Wrapped so that precision isn't lost when converting to f32
+            time_of_day: [(time_of_day % DAY_SECONDS) as f32; 4],
             tick: [tick as f32; 4],
+            sun_dir: Vec4::from(Self::sun_dir(time_of_day)).into_array(),
         }
     }
+
+    /// Get the direction pointing towards the sun at the provided time of day. The sun rises in
+    /// the east (+x), is directly overhead at midday and directly below at midnight.
+    pub fn sun_dir(time_of_day: f64) -> Vec3<f32> {
+        let angle = (time_of_day / DAY_SECONDS * std::f64::consts::PI * 2.0) as f32;
+        Vec3::new(angle.sin(), 0.0, -angle.cos())
+    }
 }
@@ -7,7 +7,8 @@ use vek::*;
 use client::Client;
 use common::{
     comp,
-    figure::Segment,
+    figure::{Segment, cell::Cell},
+    vol::{SizedVol, ReadVol, WriteVol},
     msg,
     assets,
 };
@@ -26,52 +27,154 @@ use crate::{
     anim::{
         Animation,
         Skeleton,
+        bird::{self, BirdSkeleton},
         character::{
             CharacterSkeleton,
             RunAnimation,
             IdleAnimation,
+            JumpAnimation,
+            GlideAnimation,
+            DeathAnimation,
+            AttackAnimation,
         },
+        quadruped::{self, QuadrupedSkeleton},
     },
     mesh::Meshable,
 };
 
-pub struct Figures {
-    test_model: Model<FigurePipeline>,
-    states: HashMap<EcsEntity, FigureState<CharacterSkeleton>>,
+// Models that haven't been used for this many ticks are dropped from the cache
+const MODEL_CACHE_LIFETIME: u64 = 600;
+
+// The colours that armour parts of each material are tinted with
+const LEATHER: Rgb<u8> = Rgb { r: 120, g: 80, b: 45 };
+const IRON: Rgb<u8> = Rgb { r: 150, g: 155, b: 165 };
+
+/// The parts and features of a character that its model is built from. Characters that only
+/// differ in ways the model doesn't show, such as race or gender, share a model.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct CharacterKey {
+    head: comp::character::Head,
+    chest: comp::character::Chest,
+    belt: comp::character::Belt,
+    pants: comp::character::Pants,
+    arms: comp::character::Arms,
+    feet: comp::character::Feet,
+    // All weapons share a model for now, so only whether there is one matters
+    armed: bool,
+    hair_style: u8,
+    hair: Rgb<u8>,
+    skin: Rgb<u8>,
+    eyes: Rgb<u8>,
 }
 
-impl Figures {
-    pub fn new(renderer: &mut Renderer) -> Self {
-        // TODO: Make a proper asset loading system
-        fn load_segment(filename: &'static str) -> Segment {
-            let fullpath: String = ["/voxygen/voxel/", filename].concat();
-            Segment::from(dot_vox::load_bytes(
-                assets::load(fullpath.as_str())
-                    .expect("Error loading file")
-                    .as_slice(),
-            ).unwrap())
+/// Identifies the model of a body.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum FigureKey {
+    Humanoid(CharacterKey),
+    Quadruped(comp::body::quadruped::Body),
+    Bird(comp::body::bird::Body),
+}
+
+impl From<comp::Body> for FigureKey {
+    fn from(body: comp::Body) -> Self {
+        match body {
+            comp::Body::Humanoid(character) => FigureKey::Humanoid(CharacterKey {
+                head: character.head,
+                chest: character.chest,
+                belt: character.belt,
+                pants: character.pants,
+                arms: character.arms,
+                feet: character.feet,
+                armed: character.weapon != comp::character::Weapon::None,
+                hair_style: character.hair_style,
+                hair: character.hair_rgb(),
+                skin: character.skin_rgb(),
+                eyes: character.eye_rgb(),
+            }),
+            comp::Body::Quadruped(body) => FigureKey::Quadruped(body),
+            comp::Body::Bird(body) => FigureKey::Bird(body),
         }
+    }
+}
 
-        let bone_meshes = [
+/// Caches figure models by appearance, so that creatures that look the same share a model and
+/// models are only meshed when a new appearance is first seen.
+struct FigureModelCache {
+    models: HashMap<FigureKey, (Model<FigurePipeline>, u64)>,
+}
 
-            Some(load_segment("head.vox").generate_mesh(Vec3::new(-3.5, -7.0, -6.0))),
-            Some(load_segment("chest.vox").generate_mesh(Vec3::new(-3.0, -6.0, 0.0))),
-            Some(load_segment("belt.vox").generate_mesh(Vec3::new(-3.0, -5.0, 0.0))),
-            Some(load_segment("pants.vox").generate_mesh(Vec3::new(-3.0, -5.0, 0.0))),
-            Some(load_segment("hand.vox").generate_mesh(Vec3::new(0.0, -2.0, -6.0))),
-            Some(load_segment("hand.vox").generate_mesh(Vec3::new(0.0, -2.0, -6.0))),
-            Some(load_segment("foot.vox").generate_mesh(Vec3::new(-4.0, -2.5, -6.0))),
-            Some(load_segment("foot.vox").generate_mesh(Vec3::new(-4.0, -2.5, -6.0))),
-            Some(load_segment("sword.vox").generate_mesh(Vec3::new(0.0, -0.0, 0.0))),
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-        ];
+impl FigureModelCache {
+    fn new() -> Self {
+        Self {
+            models: HashMap::new(),
+        }
+    }
+
+    /// Make sure a model for the given appearance exists, marking it as used at `tick`.
+    fn prepare(&mut self, renderer: &mut Renderer, body: comp::Body, tick: u64) {
+        let key = FigureKey::from(body);
+        match self.models.get_mut(&key) {
+            Some((_, last_used)) => *last_used = tick,
+            None => {
+                let mesh = match key {
+                    FigureKey::Humanoid(character) => Self::mesh_character(character),
+                    FigureKey::Quadruped(quadruped) => Self::mesh_quadruped(quadruped),
+                    FigureKey::Bird(bird) => Self::mesh_bird(bird),
+                };
+                let model = renderer.create_model(&mesh).unwrap();
+                self.models.insert(key, (model, tick));
+            }
+        }
+    }
+
+    fn get(&self, body: &comp::Body) -> Option<&Model<FigurePipeline>> {
+        self.models.get(&FigureKey::from(*body)).map(|(model, _)| model)
+    }
+
+    /// Drop models that haven't been used recently.
+    fn clean(&mut self, tick: u64) {
+        self.models.retain(|_, (_, last_used)| *last_used + MODEL_CACHE_LIFETIME > tick);
+    }
+
+    // TODO: Make a proper asset loading system
+    fn load_segment(filename: &'static str) -> Segment {
+        let fullpath: String = ["/voxygen/voxel/", filename].concat();
+        Segment::from(dot_vox::load_bytes(
+            assets::load(fullpath.as_str())
+                .expect("Error loading file")
+                .as_slice(),
+        ).unwrap())
+    }
 
+    fn load_mesh(filename: &'static str, position: Vec3<f32>) -> Mesh<FigurePipeline> {
+        Self::load_segment(filename).generate_mesh(position)
+    }
+
+    /// Load a model and recolour it in the given colour, keeping the model's shading. This lets
+    /// parts of different materials share a model.
+    fn load_tinted_mesh(filename: &'static str, color: Rgb<u8>, position: Vec3<f32>) -> Mesh<FigurePipeline> {
+        let mut segment = Self::load_segment(filename);
+        for pos in segment.iter_positions() {
+            let shade = match segment.get(pos).ok().and_then(Cell::get_color) {
+                Some(col) => col.map(|e| e as u32).sum() / 3,
+                None => continue,
+            };
+            let tinted = color.map(|e| (e as u32 * (shade + 128) / 255).min(255) as u8);
+            let _ = segment.set(pos, Cell::new(tinted));
+        }
+        segment.generate_mesh(position)
+    }
+
+    /// Make the mesh of a solid box of the given size and colour, centred on the bone's origin
+    /// (plus `offset`).
+    fn box_mesh(size: Vec3<u32>, color: Rgb<u8>, offset: Vec3<f32>) -> Mesh<FigurePipeline> {
+        Segment::filled(size, Cell::new(color), ())
+            .generate_mesh(offset - size.map(|e| e as f32) / 2.0)
+    }
+
+    /// Combine the meshes of a figure's parts into one mesh, with each part assigned to the bone
+    /// of the same index.
+    fn assemble(bone_meshes: &[Option<Mesh<FigurePipeline>>]) -> Mesh<FigurePipeline> {
         let mut mesh = Mesh::new();
         bone_meshes
             .iter()
@@ -80,48 +183,261 @@ impl Figures {
             .for_each(|(i, bone_mesh)| {
                 mesh.push_mesh_map(bone_mesh, |vert| vert.with_bone_idx(i as u8))
             });
+        mesh
+    }
+
+    /// Assemble the mesh of a character from its parts, with each part assigned to its bone.
+    fn mesh_character(character: CharacterKey) -> Mesh<FigurePipeline> {
+        use comp::character::{Head, Chest, Belt, Pants, Arms, Feet};
 
+        let hand = || {
+            let offset = Vec3::new(0.0, -2.0, -6.0);
+            match character.arms {
+                Arms::Bare => Self::load_tinted_mesh("hand.vox", character.skin, offset),
+                Arms::LeatherGloves => Self::load_tinted_mesh("hand.vox", LEATHER, offset),
+                Arms::IronGauntlets => Self::load_tinted_mesh("hand.vox", IRON, offset),
+            }
+        };
+        let foot = || {
+            let offset = Vec3::new(-4.0, -2.5, -6.0);
+            match character.feet {
+                Feet::Shoes => Self::load_mesh("foot.vox", offset),
+                Feet::LeatherBoots => Self::load_tinted_mesh("foot.vox", LEATHER, offset),
+                Feet::IronBoots => Self::load_tinted_mesh("foot.vox", IRON, offset),
+            }
+        };
+
+        let head = {
+            let mut head = Self::load_tinted_mesh("head.vox", character.skin, Vec3::new(-3.5, -7.0, -6.0));
+            // Figures face along the x axis
+            for eye_y in &[-4.5, -1.5] {
+                head.push_mesh(&Self::box_mesh(Vec3::new(1, 1, 1), character.eyes, Vec3::new(4.0, *eye_y, 1.0)));
+            }
+            // TODO: Use models for hair and headgear once we have them. Headgear covers the hair.
+            let top = match character.head {
+                Head::Bare => match character.hair_style {
+                    // Short
+                    0 => vec![(Vec3::new(8, 8, 2), Vec3::new(0.0, -3.0, 4.0))],
+                    // Long
+                    1 => vec![
+                        (Vec3::new(8, 8, 2), Vec3::new(0.0, -3.0, 4.0)),
+                        (Vec3::new(2, 8, 7), Vec3::new(-4.0, -3.0, 0.5)),
+                    ],
+                    // Mohawk
+                    2 => vec![(Vec3::new(6, 2, 3), Vec3::new(0.0, -3.0, 4.5))],
+                    // Bald
+                    _ => Vec::new(),
+                }
+                    .into_iter()
+                    .map(|(size, offset)| Self::box_mesh(size, character.hair, offset))
+                    .collect(),
+                Head::LeatherCap => vec![Self::box_mesh(Vec3::new(8, 8, 2), LEATHER, Vec3::new(0.0, -3.0, 4.0))],
+                Head::IronHelm => vec![Self::box_mesh(Vec3::new(9, 9, 4), IRON, Vec3::new(0.0, -3.0, 3.0))],
+            };
+            for mesh in &top {
+                head.push_mesh(mesh);
+            }
+            head
+        };
+
+        let chest_offset = Vec3::new(-3.0, -6.0, 0.0);
+        let lower_offset = Vec3::new(-3.0, -5.0, 0.0);
+        let bone_meshes = [
+            Some(head),
+            Some(match character.chest {
+                Chest::Shirt => Self::load_mesh("chest.vox", chest_offset),
+                Chest::LeatherVest => Self::load_tinted_mesh("chest.vox", LEATHER, chest_offset),
+                Chest::IronChestplate => Self::load_tinted_mesh("chest.vox", IRON, chest_offset),
+            }),
+            Some(match character.belt {
+                Belt::Cloth => Self::load_mesh("belt.vox", lower_offset),
+                Belt::Leather => Self::load_tinted_mesh("belt.vox", LEATHER, lower_offset),
+            }),
+            Some(match character.pants {
+                Pants::Cloth => Self::load_mesh("pants.vox", lower_offset),
+                Pants::Leather => Self::load_tinted_mesh("pants.vox", LEATHER, lower_offset),
+                Pants::IronGreaves => Self::load_tinted_mesh("pants.vox", IRON, lower_offset),
+            }),
+            Some(hand()),
+            Some(hand()),
+            Some(foot()),
+            Some(foot()),
+            if character.armed {
+                // TODO: Use each weapon's own model once we have them
+                Some(Self::load_mesh("sword.vox", Vec3::new(0.0, -0.0, 0.0)))
+            } else {
+                None
+            },
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ];
+
+        Self::assemble(&bone_meshes)
+    }
+
+    // TODO: Use voxel models for creatures once we have them
+    fn mesh_quadruped(body: comp::body::quadruped::Body) -> Mesh<FigurePipeline> {
+        use comp::body::quadruped::{Species, TORSO_SIZE};
+
+        let (fur, skin) = match body.species {
+            Species::Wolf => (Rgb::new(110, 110, 120), Rgb::new(80, 80, 90)),
+            Species::Boar => (Rgb::new(100, 65, 40), Rgb::new(70, 45, 30)),
+            Species::Pig => (Rgb::new(235, 165, 165), Rgb::new(220, 140, 145)),
+        };
+        let leg = || Some(Self::box_mesh(Vec3::new(3, 3, 6), skin, Vec3::new(0.0, 0.0, -3.0)));
+
+        Self::assemble(&[
+            Some(Self::box_mesh(Vec3::new(6, 6, 6), fur, Vec3::new(2.0, 0.0, 0.0))),
+            Some(Self::box_mesh(TORSO_SIZE, fur, Vec3::zero())),
+            Some(Self::box_mesh(Vec3::new(6, 2, 2), skin, Vec3::new(-3.0, 0.0, 0.0))),
+            leg(),
+            leg(),
+            leg(),
+            leg(),
+        ])
+    }
+
+    fn mesh_bird(body: comp::body::bird::Body) -> Mesh<FigurePipeline> {
+        use comp::body::bird::Species;
+
+        let (feathers, beak) = match body.species {
+            Species::Crow => (Rgb::new(30, 30, 40), Rgb::new(50, 50, 50)),
+            Species::Chicken => (Rgb::new(240, 240, 230), Rgb::new(230, 170, 40)),
+        };
+        let foot = || Some(Self::box_mesh(Vec3::new(1, 1, 3), beak, Vec3::new(0.0, 0.0, -1.5)));
+
+        Self::assemble(&[
+            Some(Self::box_mesh(Vec3::new(4, 4, 4), feathers, Vec3::zero())),
+            Some(Self::box_mesh(Vec3::new(8, 6, 6), feathers, Vec3::zero())),
+            Some(Self::box_mesh(Vec3::new(4, 4, 1), feathers, Vec3::new(-2.0, 0.0, 0.0))),
+            Some(Self::box_mesh(Vec3::new(6, 5, 1), feathers, Vec3::new(0.0, 2.5, 0.0))),
+            Some(Self::box_mesh(Vec3::new(6, 5, 1), feathers, Vec3::new(0.0, -2.5, 0.0))),
+            foot(),
+            foot(),
+        ])
+    }
+}
+
+pub struct Figures {
+    model_cache: FigureModelCache,
+    character_states: HashMap<EcsEntity, FigureState<CharacterSkeleton>>,
+    quadruped_states: HashMap<EcsEntity, FigureState<QuadrupedSkeleton>>,
+    bird_states: HashMap<EcsEntity, FigureState<BirdSkeleton>>,
+}
+
+impl Figures {
+    pub fn new(renderer: &mut Renderer) -> Self {
         Self {
-            test_model: renderer.create_model(&mesh).unwrap(),
-            states: HashMap::new(),
+            model_cache: FigureModelCache::new(),
+            character_states: HashMap::new(),
+            quadruped_states: HashMap::new(),
+            bird_states: HashMap::new(),
         }
     }
 
     pub fn maintain(&mut self, renderer: &mut Renderer, client: &mut Client) {
         let time = client.state().get_time();
+        let tick = client.get_tick();
         let ecs = client.state_mut().ecs_mut().internal_mut();
-        for (entity, pos, dir, character, animation_history) in (
+        for (entity, pos, dir, body, animation_history) in (
             &ecs.entities(),
             &ecs.read_storage::<comp::phys::Pos>(),
             &ecs.read_storage::<comp::phys::Dir>(),
-            &ecs.read_storage::<comp::Character>(),
+            &ecs.read_storage::<comp::Body>(),
             &ecs.read_storage::<comp::AnimationHistory>(),
         ).join() {
-            let state = self.states
-                .entry(entity)
-                .or_insert_with(|| FigureState::new(renderer, CharacterSkeleton::new()));
+            self.model_cache.prepare(renderer, *body, tick);
 
-            let target_skeleton = match animation_history.current {
-                comp::character::Animation::Idle => IdleAnimation::update_skeleton(&mut state.skeleton, time),
-                comp::character::Animation::Run => RunAnimation::update_skeleton(&mut state.skeleton, time),
-            };
+            match body {
+                comp::Body::Humanoid(_) => {
+                    let state = self.character_states
+                        .entry(entity)
+                        .or_insert_with(|| FigureState::new(renderer, CharacterSkeleton::new()));
 
-            state.skeleton.interpolate(&target_skeleton);
+                    let target_skeleton = match animation_history.current {
+                        comp::character::Animation::Idle => IdleAnimation::update_skeleton(&mut state.skeleton, time),
+                        comp::character::Animation::Run => RunAnimation::update_skeleton(&mut state.skeleton, time),
+                        comp::character::Animation::Jump => JumpAnimation::update_skeleton(&mut state.skeleton, time),
+                        comp::character::Animation::Glide => GlideAnimation::update_skeleton(&mut state.skeleton, time),
+                        comp::character::Animation::Attack => AttackAnimation::update_skeleton(&mut state.skeleton, time),
+                        comp::character::Animation::Death => DeathAnimation::update_skeleton(&mut state.skeleton, time),
+                    };
 
-            state.update(renderer, pos.0, dir.0);
+                    state.skeleton.interpolate(&target_skeleton);
+                    state.update(renderer, pos.0, dir.0);
+                },
+                // Creatures only know how to stand around and run, so everything else is idling
+                comp::Body::Quadruped(_) => {
+                    let state = self.quadruped_states
+                        .entry(entity)
+                        .or_insert_with(|| FigureState::new(renderer, QuadrupedSkeleton::new()));
+
+                    let target_skeleton = match animation_history.current {
+                        comp::character::Animation::Run => quadruped::RunAnimation::update_skeleton(&state.skeleton, time),
+                        _ => quadruped::IdleAnimation::update_skeleton(&state.skeleton, time),
+                    };
+
+                    state.skeleton.interpolate(&target_skeleton);
+                    state.update(renderer, pos.0, dir.0);
+                },
+                comp::Body::Bird(_) => {
+                    let state = self.bird_states
+                        .entry(entity)
+                        .or_insert_with(|| FigureState::new(renderer, BirdSkeleton::new()));
+
+                    let target_skeleton = match animation_history.current {
+                        comp::character::Animation::Run => bird::RunAnimation::update_skeleton(&state.skeleton, time),
+                        _ => bird::IdleAnimation::update_skeleton(&state.skeleton, time),
+                    };
+
+                    state.skeleton.interpolate(&target_skeleton);
+                    state.update(renderer, pos.0, dir.0);
+                },
+            }
         }
 
-        self.states.retain(|entity, _| ecs.entities().is_alive(*entity));
+        // Drop the states of entities that are gone or whose body has changed kind
+        let bodies = ecs.read_storage::<comp::Body>();
+        self.character_states.retain(|entity, _| match bodies.get(*entity) {
+            Some(comp::Body::Humanoid(_)) => true,
+            _ => false,
+        });
+        self.quadruped_states.retain(|entity, _| match bodies.get(*entity) {
+            Some(comp::Body::Quadruped(_)) => true,
+            _ => false,
+        });
+        self.bird_states.retain(|entity, _| match bodies.get(*entity) {
+            Some(comp::Body::Bird(_)) => true,
+            _ => false,
+        });
+        self.model_cache.clean(tick);
     }
 
     pub fn render(&self, renderer: &mut Renderer, client: &Client, globals: &Consts<Globals>) {
-        for state in self.states.values() {
-            renderer.render_figure(
-                &self.test_model,
-                globals,
-                &state.locals,
-                &state.bone_consts,
-            );
+        let ecs = client.state().ecs().internal();
+        for (entity, body) in (
+            &ecs.entities(),
+            &ecs.read_storage::<comp::Body>(),
+        ).join() {
+            let consts = match body {
+                comp::Body::Humanoid(_) => self.character_states.get(&entity).map(FigureState::consts),
+                comp::Body::Quadruped(_) => self.quadruped_states.get(&entity).map(FigureState::consts),
+                comp::Body::Bird(_) => self.bird_states.get(&entity).map(FigureState::consts),
+            };
+
+            if let (Some((locals, bone_consts)), Some(model)) = (consts, self.model_cache.get(body)) {
+                renderer.render_figure(
+                    model,
+                    globals,
+                    locals,
+                    bone_consts,
+                );
+            }
         }
     }
 }
@@ -141,6 +457,10 @@ impl<S: Skeleton> FigureState<S> {
         }
     }
 
+    fn consts(&self) -> (&Consts<FigureLocals>, &Consts<FigureBoneData>) {
+        (&self.locals, &self.bone_consts)
+    }
+
     fn update(&mut self, renderer: &mut Renderer, pos: Vec3<f32>, dir: Vec3<f32>) {
         let mat =
             Mat4::<f32>::identity() *
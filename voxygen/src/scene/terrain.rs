@@ -62,6 +62,15 @@ fn mesh_worker(
     }
 }
 
+const NEIGHBOUR_OFFSETS: [Vec3<i32>; 6] = [
+    Vec3 { x: -1, y: 0, z: 0 },
+    Vec3 { x: 1, y: 0, z: 0 },
+    Vec3 { x: 0, y: -1, z: 0 },
+    Vec3 { x: 0, y: 1, z: 0 },
+    Vec3 { x: 0, y: 0, z: -1 },
+    Vec3 { x: 0, y: 0, z: 1 },
+];
+
 pub struct Terrain {
     chunks: HashMap<Vec3<i32>, TerrainChunk>,
 
@@ -91,14 +100,19 @@ impl Terrain {
     pub fn maintain(&mut self, renderer: &mut Renderer, client: &Client) {
         let current_tick = client.get_tick();
 
+        // A modified block on the edge of a chunk changes the face elision of its neighbours too,
+        // so changed chunks need their (loaded) neighbours to be remeshed as well
+        let changed_neighbours = client.state().changes().changed_chunks
+            .iter()
+            .flat_map(|pos| NEIGHBOUR_OFFSETS.iter().map(move |offs| *pos + *offs))
+            .filter(|pos| self.chunks.contains_key(pos))
+            .collect::<Vec<_>>();
+
         // Add any recently created or changed chunks to the list of chunks to be meshed
         for pos in client.state().changes().new_chunks.iter()
             .chain(client.state().changes().changed_chunks.iter())
+            .chain(changed_neighbours.iter())
         {
-            // TODO: ANOTHER PROBLEM HERE!
-            // What happens if the block on the edge of a chunk gets modified? We need to spawn
-            // a mesh worker to remesh its neighbour(s) too since their ambient occlusion and face
-            // elision information changes too!
             match self.mesh_todo.iter_mut().find(|todo| todo.pos == *pos) {
                 Some(todo) => todo.started_tick = current_tick,
                 // The chunk it's queued yet, add it to the queue
@@ -1,6 +1,7 @@
 use std::{cell::RefCell, rc::Rc, time::Duration};
 use vek::*;
-use common::clock::Clock;
+use log::warn;
+use common::{clock::Clock, comp, item::ItemRegistry};
 use client::{
     self,
     Client,
@@ -24,6 +25,7 @@ pub struct SessionState {
     client: Rc<RefCell<Client>>,
     key_state: KeyState,
     hud: Hud,
+    items: ItemRegistry,
 }
 
 
@@ -38,6 +40,11 @@ impl SessionState {
             client,
             key_state: KeyState::new(),
             hud: Hud::new(window),
+            // Item names are only needed for display, so a broken registry isn't fatal
+            items: ItemRegistry::load().unwrap_or_else(|err| {
+                warn!("Failed to load item definitions: {:?}", err);
+                ItemRegistry::default()
+            }),
         }
     }
 }
@@ -63,14 +70,47 @@ impl SessionState {
         let dir_vec = self.key_state.dir_vec();
         let move_dir = unit_vecs.0 * dir_vec[0] + unit_vecs.1 * dir_vec[1];
 
-        for event in self.client.borrow_mut().tick(client::Input { move_dir }, dt)? {
+        let input = client::Input {
+            move_dir,
+            gait: self.key_state.gait(),
+            jump: self.key_state.jump(),
+            glide: self.key_state.glide(),
+            attack: self.key_state.attack(),
+        };
+
+        for event in self.client.borrow_mut().tick(input, dt)? {
             match event {
                 client::Event::Chat(msg) => {
                     self.hud.new_message(msg);
                 }
+                client::Event::Damage { .. } => {},
+                client::Event::Death { .. } => {
+                    self.hud.new_message(String::from("You died!"));
+                }
+                client::Event::CharacterActionError(err) => self.hud.new_message(err),
+                client::Event::CharacterSelected => {},
             }
         }
 
+        // Keep the HUD up to date with the player's health
+        let client = self.client.borrow();
+        if let Some(stats) = client.state().read_storage::<comp::Stats>().get(client.player()) {
+            self.hud.set_health(stats.health_fraction());
+        }
+
+        // ...and the contents of their inventory
+        if let Some(inventory) = client.state().read_storage::<comp::Inventory>().get(client.player()) {
+            let items = &self.items;
+            self.hud.set_inventory(inventory
+                .slots()
+                .iter()
+                .map(|slot| slot.map(|stack| (
+                    items.get(stack.item).map(|def| def.name.clone()).unwrap_or_else(|| String::from("???")),
+                    stack.amount,
+                )))
+                .collect());
+        }
+
         Ok(())
     }
 
@@ -140,11 +180,22 @@ impl PlayState for SessionState {
                     Event::KeyDown(Key::MoveBack) => self.key_state.down = true,
                     Event::KeyDown(Key::MoveLeft) => self.key_state.left = true,
                     Event::KeyDown(Key::MoveRight) => self.key_state.right = true,
+                    Event::KeyDown(Key::Jump) => self.key_state.jump = true,
+                    Event::KeyDown(Key::Glide) => self.key_state.glide = true,
+                    Event::KeyDown(Key::Sprint) => self.key_state.sprint = true,
+                    Event::KeyDown(Key::Walk) => self.key_state.walk = true,
+                    Event::KeyDown(Key::Attack) => self.key_state.attack = true,
+                    Event::KeyDown(Key::Mount) => self.client.borrow_mut().toggle_mount(),
                     // Movement Key Released
                     Event::KeyUp(Key::MoveForward) => self.key_state.up = false,
                     Event::KeyUp(Key::MoveBack) => self.key_state.down = false,
                     Event::KeyUp(Key::MoveLeft) => self.key_state.left = false,
                     Event::KeyUp(Key::MoveRight) => self.key_state.right = false,
+                    Event::KeyUp(Key::Jump) => self.key_state.jump = false,
+                    Event::KeyUp(Key::Glide) => self.key_state.glide = false,
+                    Event::KeyUp(Key::Sprint) => self.key_state.sprint = false,
+                    Event::KeyUp(Key::Walk) => self.key_state.walk = false,
+                    Event::KeyUp(Key::Attack) => self.key_state.attack = false,
                     // Pass all other events to the scene
                     event => {
                         self.scene.handle_input_event(event);
@@ -166,6 +217,9 @@ impl PlayState for SessionState {
                         // TODO: Handle result
                         self.client.borrow_mut().send_chat(msg);
                     },
+                    HudEvent::MoveInventorySlot { from, to } => {
+                        self.client.borrow_mut().move_inventory_slot(from, to);
+                    },
                     HudEvent::Logout => return PlayStateResult::Pop,
                     HudEvent::Quit => {
                         global_state.singleplayer = None;
@@ -26,6 +26,11 @@ pub struct ControlSettings {
     pub move_left: VirtualKeyCode,
     pub move_back: VirtualKeyCode,
     pub move_right: VirtualKeyCode,
+    pub jump: VirtualKeyCode,
+    pub glide: VirtualKeyCode,
+    pub sprint: VirtualKeyCode,
+    pub walk: VirtualKeyCode,
+    pub mount: VirtualKeyCode,
     pub map: VirtualKeyCode,
     pub bag: VirtualKeyCode,
     pub quest_log: VirtualKeyCode,
@@ -48,6 +53,11 @@ impl Default for Settings {
                 move_left: VirtualKeyCode::A,
                 move_back: VirtualKeyCode::S,
                 move_right: VirtualKeyCode::D,
+                jump: VirtualKeyCode::Space,
+                glide: VirtualKeyCode::LShift,
+                sprint: VirtualKeyCode::LControl,
+                walk: VirtualKeyCode::LAlt,
+                mount: VirtualKeyCode::F,
                 map: VirtualKeyCode::M,
                 bag: VirtualKeyCode::B,
                 quest_log: VirtualKeyCode::L,
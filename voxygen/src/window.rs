@@ -43,6 +43,11 @@ impl Window {
         key_map.insert(settings.controls.move_left, Key::MoveLeft);
         key_map.insert(settings.controls.move_back, Key::MoveBack);
         key_map.insert(settings.controls.move_right, Key::MoveRight);
+        key_map.insert(settings.controls.jump, Key::Jump);
+        key_map.insert(settings.controls.glide, Key::Glide);
+        key_map.insert(settings.controls.sprint, Key::Sprint);
+        key_map.insert(settings.controls.walk, Key::Walk);
+        key_map.insert(settings.controls.mount, Key::Mount);
         key_map.insert(settings.controls.map, Key::Map);
         key_map.insert(settings.controls.bag, Key::Bag);
         key_map.insert(settings.controls.quest_log, Key::QuestLog);
@@ -101,6 +106,14 @@ impl Window {
                         events.push(Event::Resize(Vec2::new(width as u32, height as u32)));
                     }
                     glutin::WindowEvent::ReceivedCharacter(c) => events.push(Event::Char(c)),
+                    glutin::WindowEvent::MouseInput {
+                        state,
+                        button: glutin::MouseButton::Left,
+                        ..
+                    } if cursor_grabbed => events.push(match state {
+                        glutin::ElementState::Pressed => Event::KeyDown(Key::Attack),
+                        _ => Event::KeyUp(Key::Attack),
+                    }),
 
                     glutin::WindowEvent::KeyboardInput { input, .. } => match input.virtual_keycode
                     {
@@ -171,6 +184,12 @@ pub enum Key {
     MoveBack,
     MoveLeft,
     MoveRight,
+    Jump,
+    Glide,
+    Sprint,
+    Walk,
+    Mount,
+    Attack,
     Enter,
     Escape,
     Map,
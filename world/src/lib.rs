@@ -7,9 +7,11 @@ use common::{
     vol::{Vox, SizedVol, WriteVol},
     terrain::{
         Block,
+        BiomeKind,
         TerrainChunk,
         TerrainChunkMeta,
     },
+    weather::{Climate, WEATHER_CELL_SIZE},
 };
 
 #[derive(Debug)]
@@ -27,7 +29,7 @@ impl World {
     pub fn generate_chunk(chunk_pos: Vec3<i32>) -> TerrainChunk {
         // TODO: This is all test code, remove/improve this later
 
-        let mut chunk = TerrainChunk::filled(Block::empty(), TerrainChunkMeta::void());
+        let mut chunk = TerrainChunk::filled(Block::empty(), TerrainChunkMeta::new(Self::biome(chunk_pos)));
 
         let air = Block::empty();
         let stone = Block::new(1, Rgb::new(200, 220, 255));
@@ -58,4 +60,50 @@ impl World {
 
         chunk
     }
+
+    /// Get the biome of the chunk with the given key. Every chunk in a column shares a biome.
+    pub fn biome(chunk_pos: Vec3<i32>) -> BiomeKind {
+        // TODO: Shape the terrain according to its biome
+        let perlin_nz = Perlin::new();
+        let posf = Vec2::from(chunk_pos).map(|e: i32| e as f64);
+
+        let cold = perlin_nz.get((posf / 10.0 + 100.0).into_array());
+        let altitude = perlin_nz.get((posf / 8.0 + 400.0).into_array());
+        let dryness = perlin_nz.get((posf / 6.0 + 200.0).into_array());
+        let wetness = perlin_nz.get((posf / 5.0 + 500.0).into_array());
+        let trees = perlin_nz.get((posf / 4.0 + 300.0).into_array());
+
+        if cold > 0.35 {
+            BiomeKind::Snowlands
+        } else if altitude > 0.35 {
+            BiomeKind::Mountain
+        } else if dryness > 0.3 {
+            BiomeKind::Desert
+        } else if wetness > 0.35 {
+            BiomeKind::Swamp
+        } else if trees > 0.2 {
+            BiomeKind::Forest
+        } else {
+            BiomeKind::Grassland
+        }
+    }
+
+    /// Get the climate of the weather cell with the given key, which determines how likely
+    /// different kinds of weather are there. This is the average climate of the biomes of the
+    /// chunk columns in the cell.
+    pub fn climate(cell_key: Vec2<i32>) -> Climate {
+        let climates = (0..WEATHER_CELL_SIZE)
+            .flat_map(|x| (0..WEATHER_CELL_SIZE).map(move |y| Vec2::new(x, y)))
+            .map(|offs| {
+                let column = cell_key * WEATHER_CELL_SIZE + offs;
+                Climate::from(Self::biome(Vec3::new(column.x, column.y, 0)))
+            })
+            .collect::<Vec<_>>();
+
+        let count = climates.len() as f32;
+        Climate {
+            humidity: climates.iter().map(|climate| climate.humidity).sum::<f32>() / count,
+            windiness: climates.iter().map(|climate| climate.windiness).sum::<f32>() / count,
+        }
+    }
 }